@@ -0,0 +1,66 @@
+//! Benchmarks of KFM homomorphic hashing against Pedersen commitments.
+// `criterion_group!` generates undocumented public functions.
+#![allow(missing_docs)]
+
+use blstrs::Scalar;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rand::Rng;
+use zc_auth::{
+    commit::{ChunkCommitments, PedersenCommitter},
+    hash::{HashVerifier, HomomorphicHasher},
+    verify::Verifier,
+};
+use zc_rlnc::encode::Encoder;
+
+const DATA_SIZE: usize = 1024 * 4;
+const CHUNK_COUNT: usize = 4;
+const PACKET_COUNT: usize = 8;
+
+fn bench_hash(c: &mut Criterion) {
+    let data = rand::rng().random_iter().take(DATA_SIZE).collect::<Vec<u8>>();
+    let chunks = Encoder::<Scalar>::prepare(&data, CHUNK_COUNT).unwrap();
+    let symbols = chunks.layout().symbols_per_chunk;
+
+    let mut group = c.benchmark_group("hash");
+    group.sample_size(10);
+
+    group.bench_function("kfm_setup", |b| b.iter(|| HomomorphicHasher::new([0u8; 32], symbols)));
+    group.bench_function("pedersen_setup", |b| {
+        b.iter(|| PedersenCommitter::new([0u8; 32], symbols))
+    });
+
+    let hasher = HomomorphicHasher::new([0u8; 32], symbols);
+    let committer = PedersenCommitter::new([0u8; 32], symbols);
+    group.bench_function("kfm_hash_chunks", |b| b.iter(|| hasher.hash_chunks(&chunks).unwrap()));
+    group.bench_function("pedersen_commit_chunks", |b| {
+        b.iter(|| ChunkCommitments::new(&committer, &chunks).unwrap())
+    });
+
+    let hashes = hasher.hash_chunks(&chunks).unwrap();
+    let commitments = ChunkCommitments::new(&committer, &chunks).unwrap();
+    let packets = Encoder::from_chunks(chunks).encode_batch(rand::rng(), PACKET_COUNT).unwrap();
+    let kfm = HashVerifier::new(hasher, hashes);
+    let pedersen = Verifier::new(committer, commitments);
+
+    group.bench_function("kfm_verify", |b| b.iter(|| kfm.verify(&packets[0]).unwrap()));
+    group.bench_function("pedersen_verify", |b| b.iter(|| pedersen.verify(&packets[0]).unwrap()));
+    group.bench_function("kfm_verify_batch", |b| {
+        b.iter_batched(
+            rand::rng,
+            |rng| kfm.verify_batch(&packets, rng).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("pedersen_verify_batch", |b| {
+        b.iter_batched(
+            rand::rng,
+            |rng| pedersen.verify_batch(&packets, rng).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_hash);
+criterion_main!(benches);
@@ -0,0 +1,304 @@
+//! Authenticated encoding and decoding, which bundles coded packets with Pedersen commitments.
+use blstrs::Scalar;
+use group::Curve;
+use rand::Rng;
+use zc_rlnc::{
+    RLNCError,
+    decode::Decoder,
+    encode::Encoder,
+    primitives::{ChunkLayout, Chunks, ChunksError, packet::RLNCPacket, packing::Packing},
+};
+
+use crate::{
+    commit::{ChunkCommitments, PedersenCommitter},
+    sign::{PublicKey, SecretKey, SignedHeader},
+    verify::{AuthError, Verifier},
+};
+
+/// The domain separation prefix of a serialized [`GenerationHeader`].
+const HEADER_DOMAIN: &[u8] = b"ZC_GENERATION_HEADER_V1";
+
+/// The header of an authenticated generation. It holds everything a receiver needs to verify and
+/// decode the coded packets of the generation, and must be transmitted to receivers through an
+/// authenticated channel, e.g. as a [`SignedHeader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationHeader {
+    /// The seed used to derive the Pedersen generators.
+    pub seed: [u8; 32],
+    /// The chunk layout of the generation.
+    pub layout: ChunkLayout,
+    /// The commitments to the chunks.
+    pub commitments: ChunkCommitments,
+}
+
+impl GenerationHeader {
+    /// Serializes the header into the canonical byte string that's signed by the source: a
+    /// domain prefix, the seed, the chunk count and chunk size as little-endian `u64`s, the
+    /// packing, and the compressed commitments. The other layout fields are derived from these.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let commitments = self.commitments.inner();
+        let mut bytes = Vec::with_capacity(HEADER_DOMAIN.len() + 49 + commitments.len() * 48);
+
+        bytes.extend_from_slice(HEADER_DOMAIN);
+        bytes.extend_from_slice(&self.seed);
+        bytes.extend_from_slice(&(self.layout.chunk_count as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.layout.chunk_size as u64).to_le_bytes());
+        bytes.push(match self.layout.packing {
+            Packing::Bytes => 0,
+            Packing::Bits => 1,
+        });
+
+        for commitment in commitments {
+            bytes.extend_from_slice(&commitment.to_affine().to_compressed());
+        }
+
+        bytes
+    }
+
+    /// Checks that the layout fields that aren't signed match the ones derived from the signed
+    /// chunk count, chunk size and packing, so that a relay can't change them.
+    fn check_layout(&self) -> Result<(), AuthError> {
+        let ChunkLayout { chunk_count, chunk_size, packing, .. } = self.layout;
+        if packing.round_chunk_size::<Scalar>(chunk_size) != chunk_size ||
+            ChunkLayout::from_chunk_size::<Scalar>(chunk_count, chunk_size, packing) !=
+                self.layout
+        {
+            return Err(RLNCError::from(ChunksError::InvalidLayout).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// An encoder that commits to the chunks of the data before encoding, and produces a
+/// [`GenerationHeader`] along with coded packets.
+#[derive(Debug)]
+pub struct AuthenticatedEncoder {
+    encoder: Encoder<Scalar>,
+    header: GenerationHeader,
+}
+
+impl AuthenticatedEncoder {
+    /// Creates a new authenticated encoder for the given data and chunk count. The Pedersen
+    /// generators are derived from `seed`.
+    pub fn new(
+        data: impl AsRef<[u8]>,
+        chunk_count: usize,
+        seed: [u8; 32],
+    ) -> Result<Self, AuthError> {
+        Self::from_chunks(Encoder::prepare(data, chunk_count)?, seed)
+    }
+
+    /// Creates a new authenticated encoder from prepared chunks. The Pedersen generators are
+    /// derived from `seed`.
+    pub fn from_chunks(chunks: Chunks<Scalar>, seed: [u8; 32]) -> Result<Self, AuthError> {
+        let committer = PedersenCommitter::new(seed, chunks.layout().symbols_per_chunk);
+        Self::with_committer(chunks, &committer)
+    }
+
+    /// Creates a new authenticated encoder from prepared chunks and an existing committer (e.g.
+    /// to reuse precomputed tables). The header carries the committer's seed.
+    pub fn with_committer(
+        chunks: Chunks<Scalar>,
+        committer: &PedersenCommitter,
+    ) -> Result<Self, AuthError> {
+        let commitments = ChunkCommitments::new(committer, &chunks)?;
+        let header =
+            GenerationHeader { seed: *committer.seed(), layout: chunks.layout(), commitments };
+
+        Ok(Self { encoder: Encoder::from_chunks(chunks), header })
+    }
+
+    /// Returns the header of the generation.
+    pub const fn header(&self) -> &GenerationHeader {
+        &self.header
+    }
+
+    /// Returns the header of the generation, signed with the source's secret key.
+    pub fn signed_header(&self, key: &SecretKey) -> SignedHeader {
+        SignedHeader::sign(self.header.clone(), key)
+    }
+
+    /// Returns the underlying encoder.
+    pub const fn encoder(&self) -> &Encoder<Scalar> {
+        &self.encoder
+    }
+
+    /// Encodes the data with a random coding vector, using the provided random number generator.
+    pub fn encode<R: Rng>(&self, rng: R) -> Result<RLNCPacket<Scalar>, AuthError> {
+        Ok(self.encoder.encode(rng)?)
+    }
+
+    /// Encodes `count` packets with random coding vectors at once.
+    pub fn encode_batch<R: Rng>(
+        &self,
+        rng: R,
+        count: usize,
+    ) -> Result<Vec<RLNCPacket<Scalar>>, AuthError> {
+        Ok(self.encoder.encode_batch(rng, count)?)
+    }
+}
+
+/// A decoder that verifies every coded packet against the commitments in the
+/// [`GenerationHeader`] before adding it to the decoding matrix, so polluted packets are rejected
+/// before they can corrupt the decoder state.
+#[derive(Debug)]
+pub struct AuthenticatedDecoder {
+    decoder: Decoder<Scalar>,
+    verifier: Verifier,
+}
+
+impl AuthenticatedDecoder {
+    /// Creates a new authenticated decoder for the given generation header, deriving the Pedersen
+    /// generators from its seed.
+    pub fn new(header: GenerationHeader) -> Result<Self, AuthError> {
+        header.check_layout()?;
+        let committer = PedersenCommitter::new(header.seed, header.layout.symbols_per_chunk);
+        Self::with_committer(header, committer)
+    }
+
+    /// Creates a new authenticated decoder for a signed generation header, after checking that
+    /// it was signed by the claimed source's public key.
+    pub fn from_signed(signed: SignedHeader, key: &PublicKey) -> Result<Self, AuthError> {
+        signed.verify(key)?;
+        Self::new(signed.header)
+    }
+
+    /// Creates a new authenticated decoder for the given generation header and an existing
+    /// committer, which must have been created from the header's seed. Headers whose derived
+    /// layout fields don't match the signed ones are rejected.
+    pub fn with_committer(
+        header: GenerationHeader,
+        committer: PedersenCommitter,
+    ) -> Result<Self, AuthError> {
+        if committer.seed() != &header.seed {
+            return Err(AuthError::SeedMismatch);
+        }
+        header.check_layout()?;
+
+        let layout = header.layout;
+        if header.commitments.len() != layout.chunk_count {
+            return Err(AuthError::InvalidCodingVectorLength(
+                header.commitments.len(),
+                layout.chunk_count,
+            ));
+        }
+
+        if committer.len() != layout.symbols_per_chunk {
+            return Err(AuthError::InvalidSymbolCount(committer.len(), layout.symbols_per_chunk));
+        }
+
+        Ok(Self {
+            decoder: Decoder::from_layout(layout)?,
+            verifier: Verifier::new(committer, header.commitments),
+        })
+    }
+
+    /// Verifies and decodes a coded packet. If the decoder has enough linearly independent
+    /// packets, it will return the original data. Invalid packets are rejected with an error and
+    /// don't affect the decoder.
+    pub fn decode(&mut self, packet: RLNCPacket<Scalar>) -> Result<Option<Vec<u8>>, AuthError> {
+        self.verifier.verify(&packet)?;
+        Ok(self.decoder.decode(packet)?)
+    }
+
+    /// Verifies a coded packet and adds it to the decoder without decoding. Returns true if the
+    /// decoder has enough linearly independent packets to decode the original data.
+    pub fn push(&mut self, packet: RLNCPacket<Scalar>) -> Result<bool, AuthError> {
+        self.verifier.verify(&packet)?;
+        Ok(self.decoder.push(packet)?)
+    }
+
+    /// Verifies a batch of coded packets at once and adds them to the decoder. If any packet is
+    /// invalid, none of them are added and [`AuthError::InvalidPackets`] is returned.
+    pub fn push_batch<R: Rng>(
+        &mut self,
+        packets: Vec<RLNCPacket<Scalar>>,
+        rng: R,
+    ) -> Result<bool, AuthError> {
+        self.verifier.verify_batch(&packets, rng)?;
+
+        for packet in packets {
+            self.decoder.push(packet)?;
+        }
+
+        Ok(self.decoder.can_decode())
+    }
+
+    /// Writes the original data into the caller-provided buffer, see [`Decoder::decode_into`].
+    pub fn decode_into(&self, out: &mut [u8]) -> Result<usize, AuthError> {
+        Ok(self.decoder.decode_into(out)?)
+    }
+
+    /// Returns the underlying decoder.
+    pub const fn decoder(&self) -> &Decoder<Scalar> {
+        &self.decoder
+    }
+
+    /// Returns the verifier.
+    pub const fn verifier(&self) -> &Verifier {
+        &self.verifier
+    }
+
+    /// Returns the number of linearly independent packets received.
+    pub const fn rank(&self) -> usize {
+        self.decoder.rank()
+    }
+
+    /// Returns true if the decoder can decode the original data.
+    pub const fn can_decode(&self) -> bool {
+        self.decoder.can_decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use group::ff::Field;
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_authenticated_encode_decode() {
+        let data = rand::rng().random_iter().take(1024 * 32).collect::<Vec<_>>();
+        let chunk_count = 4;
+
+        let encoder = AuthenticatedEncoder::new(&data, chunk_count, [7u8; 32]).unwrap();
+        let mut decoder = AuthenticatedDecoder::new(encoder.header().clone()).unwrap();
+
+        let mut polluted = encoder.encode(rand::rng()).unwrap();
+        polluted.data[1] += Scalar::ONE;
+        assert!(matches!(decoder.decode(polluted.clone()), Err(AuthError::InvalidPacket)));
+        assert_eq!(decoder.rank(), 0);
+
+        let mut batch = encoder.encode_batch(rand::rng(), 2).unwrap();
+        batch.push(polluted);
+        assert!(matches!(
+            decoder.push_batch(batch, rand::rng()),
+            Err(AuthError::InvalidPackets(indices)) if indices == [2]
+        ));
+        assert_eq!(decoder.rank(), 0);
+
+        let decoded = loop {
+            if let Some(decoded) = decoder.decode(encoder.encode(rand::rng()).unwrap()).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+
+        // A header with a different seed rejects all packets.
+        let mut header = encoder.header().clone();
+        header.seed = [8u8; 32];
+        let mut decoder = AuthenticatedDecoder::new(header).unwrap();
+        assert!(decoder.push(encoder.encode(rand::rng()).unwrap()).is_err());
+
+        // A committer from another seed is rejected upfront.
+        let committer =
+            PedersenCommitter::new([8u8; 32], encoder.header().layout.symbols_per_chunk);
+        assert!(matches!(
+            AuthenticatedDecoder::with_committer(encoder.header().clone(), committer),
+            Err(AuthError::SeedMismatch)
+        ));
+    }
+}
@@ -1,10 +1,28 @@
 //! This module implements non-hiding Pedersen commitments.
-use blstrs::{G1Projective, Scalar};
+use std::ops::Range;
+
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::Curve;
+use rand::Rng;
+#[cfg(feature = "parallel")]
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use sha2::{Digest, Sha256};
+
+use zc_rlnc::primitives::Chunks;
+
+use crate::{table::FixedBaseTable, verify::AuthError};
 
 /// The domain separation tag for the Pedersen commitment scheme.
-/// Used in the hash_to_curve operation.
-const DST: &[u8] = b"RLNC_PEDERSEN_GEN";
+/// Used in the `hash_to_curve` operation.
+///
+/// The tag is versioned: any change to the derivation of the generators (see
+/// [`PedersenCommitter::new`]) must bump it, so that committers with different generators can't
+/// be confused.
+pub const DST: &[u8] = b"RLNC_PEDERSEN_GEN_V1";
+
+/// The maximum window size supported by [`PedersenCommitter::with_precomputation`]. Tables with
+/// this window size already take 3MiB per generator.
+pub const MAX_WINDOW_BITS: usize = 16;
 
 /// A committer that uses the non-hiding Pedersen commitment scheme.
 ///
@@ -18,45 +36,364 @@ const DST: &[u8] = b"RLNC_PEDERSEN_GEN";
 /// # Security
 /// The security of the Pedersen commitment scheme relies on the discrete logarithm assumption.
 /// The generators are chosen such that the discrete logarithm of the commitment to a symbol is
-/// hard to compute.
+/// hard to compute. The commitments are binding, but not hiding: see
+/// [`HidingCommitter`](crate::hiding::HidingCommitter) for a variant with blinding factors.
+///
+/// # Precomputation
+/// Since the generators are fixed, the committer can optionally precompute windowed fixed-base
+/// tables at construction time (see [`PedersenCommitter::with_precomputation`]). This makes
+/// repeated commitments (and therefore verifications) faster, at the cost of memory.
 #[derive(Debug)]
 pub struct PedersenCommitter {
+    seed: [u8; 32],
     generators: Vec<G1Projective>,
+    table: Option<FixedBaseTable>,
 }
 
 impl PedersenCommitter {
     /// Creates a new deterministic committer with the given seed and number of generators.
+    ///
+    /// The generator with index `i` is
+    ///
+    /// ```text
+    /// Gᵢ = hash_to_curve(seed || u64_le(i), DST)
+    /// ```
+    ///
+    /// with the `BLS12381G1_XMD:SHA-256_SSWU_RO_` suite and [`DST`], so it's the same on every
+    /// platform (including 32-bit targets like `wasm32`).
     pub fn new(seed: [u8; 32], n: usize) -> Self {
-        #[cfg(feature = "parallel")]
-        let generators = (0..n)
-            .into_par_iter()
-            .map(|i| {
-                let mut msg = [0u8; 40];
-                msg[..32].copy_from_slice(&seed);
-                msg[32..].copy_from_slice(&i.to_le_bytes());
+        Self { seed, generators: derive_generators(&seed, 0..n), table: None }
+    }
+
+    /// Loads a committer from the serialized generators of a committer with the given seed,
+    /// see [`PedersenCommitter::to_bytes`]. This is cheaper than deriving the generators again
+    /// with [`PedersenCommitter::new`], since it skips hashing to the curve.
+    ///
+    /// # Security
+    /// The checksum only protects against corruption and mismatched seeds: anyone can compute it
+    /// for arbitrary points. Commitments are only binding if nobody knows the discrete logarithms
+    /// between the generators, so generators from untrusted sources must be derived with
+    /// [`PedersenCommitter::new`] instead.
+    pub fn from_bytes(seed: &[u8; 32], bytes: &[u8]) -> Result<Self, AuthError> {
+        let Some((header, points)) = bytes.split_first_chunk::<72>() else {
+            return Err(AuthError::InvalidEncoding);
+        };
+
+        let n = u64::from_le_bytes(header[32..40].try_into().unwrap());
+        if header[..32] != *seed || n.checked_mul(48) != Some(points.len() as u64) {
+            return Err(AuthError::InvalidEncoding);
+        }
+
+        if header[40..] != checksum(seed, points) {
+            return Err(AuthError::InvalidEncoding);
+        }
 
-                G1Projective::hash_to_curve(&msg, DST, &[])
-            })
-            .collect();
+        // The subgroup checks are skipped: they're as expensive as deriving the generators, and
+        // don't make untrusted generators safe to use anyway.
+        let decompress = |bytes: &[u8]| {
+            Option::<G1Affine>::from(G1Affine::from_compressed_unchecked(bytes.try_into().unwrap()))
+                .map(G1Projective::from)
+        };
+
+        #[cfg(feature = "parallel")]
+        let generators = {
+            use rayon::prelude::*;
+            points.par_chunks_exact(48).map(decompress).collect::<Option<Vec<_>>>()
+        };
 
         #[cfg(not(feature = "parallel"))]
-        let generators = (0..n)
-            .map(|i| {
-                let mut msg = [0u8; 40];
-                msg[..32].copy_from_slice(&seed);
-                msg[32..].copy_from_slice(&i.to_le_bytes());
+        let generators = points.chunks_exact(48).map(decompress).collect::<Option<Vec<_>>>();
+
+        let generators = generators.ok_or(AuthError::InvalidEncoding)?;
+        Ok(Self { seed: *seed, generators, table: None })
+    }
+
+    /// Serializes the generators as the seed, the number of generators (u64 little-endian), a
+    /// SHA-256 checksum over the seed and the points, and the compressed points.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut generators = vec![G1Affine::default(); self.generators.len()];
+        G1Projective::batch_normalize(&self.generators, &mut generators);
+
+        let points = generators.iter().flat_map(G1Affine::to_compressed).collect::<Vec<_>>();
+
+        let mut bytes = Vec::with_capacity(72 + points.len());
+        bytes.extend_from_slice(&self.seed);
+        bytes.extend_from_slice(&(self.generators.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&checksum(&self.seed, &points));
+        bytes.extend_from_slice(&points);
+
+        bytes
+    }
+
+    /// Extends the committer to `n` generators, deriving only the missing ones, e.g. when the
+    /// chunk size of later generations grows. Does nothing if the committer already has at least
+    /// `n` generators. Precomputed tables are rebuilt for all generators.
+    pub fn extend(&mut self, n: usize) {
+        if n <= self.generators.len() {
+            return;
+        }
 
-                G1Projective::hash_to_curve(&msg, DST, &[])
-            })
-            .collect();
+        let new = derive_generators(&self.seed, self.generators.len()..n);
+        self.generators.extend(new);
 
-        Self { generators }
+        if let Some(window_bits) = self.table.as_ref().map(FixedBaseTable::window_bits) {
+            self.precompute(window_bits);
+        }
+    }
+
+    /// Creates a new deterministic committer with the given seed and number of generators, and
+    /// precomputes fixed-base tables with the given window size (in bits) for every generator.
+    ///
+    /// The window size is the memory/speed knob: the tables take `n * 2^(window_bits - 1) * 96`
+    /// bytes, e.g. 12MiB for 1024 generators with 8 bit windows. Larger windows make commitments
+    /// faster, but take exponentially longer to precompute. Window sizes between 6 and 10 bits
+    /// are a good default.
+    ///
+    /// # Panics
+    /// Panics if `window_bits` is 0 or larger than [`MAX_WINDOW_BITS`].
+    pub fn with_precomputation(seed: [u8; 32], n: usize, window_bits: usize) -> Self {
+        let mut committer = Self::new(seed, n);
+        committer.precompute(window_bits);
+        committer
+    }
+
+    /// Precomputes fixed-base tables with the given window size for every generator.
+    fn precompute(&mut self, window_bits: usize) {
+        let mut generators = vec![G1Affine::default(); self.generators.len()];
+        G1Projective::batch_normalize(&self.generators, &mut generators);
+        self.table = Some(FixedBaseTable::new(&generators, window_bits));
+    }
+
+    /// Returns the seed the generators are derived from.
+    pub const fn seed(&self) -> &[u8; 32] {
+        &self.seed
+    }
+
+    /// Returns the number of generators (i.e. the number of symbols that can be committed to).
+    pub const fn len(&self) -> usize {
+        self.generators.len()
+    }
+
+    /// Returns true if the committer has no generators.
+    pub const fn is_empty(&self) -> bool {
+        self.generators.is_empty()
+    }
+
+    /// Returns the size of the precomputed tables in bytes, or 0 if there are none.
+    pub fn precomputed_size(&self) -> usize {
+        self.table.as_ref().map_or(0, FixedBaseTable::size)
     }
 
     /// Commits to the symbols using the committer's generators.
     pub fn commit(&self, symbols: &[Scalar]) -> G1Projective {
         assert_eq!(symbols.len(), self.generators.len());
 
-        G1Projective::multi_exp(&self.generators, symbols)
+        match &self.table {
+            Some(table) => table.multi_exp(symbols),
+            None => G1Projective::multi_exp(&self.generators, symbols),
+        }
+    }
+}
+
+/// The Pedersen commitments to the chunks of a generation, in chunk order. Coded packets are
+/// verified against these, see [`Verifier`](crate::verify::Verifier).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkCommitments(Vec<G1Projective>);
+
+impl ChunkCommitments {
+    /// Commits to every chunk with the given committer.
+    pub fn new(committer: &PedersenCommitter, chunks: &Chunks<Scalar>) -> Result<Self, AuthError> {
+        let symbols = chunks.layout().symbols_per_chunk;
+        if symbols != committer.len() {
+            return Err(AuthError::InvalidSymbolCount(symbols, committer.len()));
+        }
+
+        Ok(Self(chunks.inner().iter().map(|c| committer.commit(c.symbols())).collect()))
+    }
+
+    /// Returns the commitments.
+    pub fn inner(&self) -> &[G1Projective] {
+        &self.0
+    }
+
+    /// Returns the number of commitments (i.e. the chunk count).
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no commitments.
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<G1Projective>> for ChunkCommitments {
+    fn from(commitments: Vec<G1Projective>) -> Self {
+        Self(commitments)
+    }
+}
+
+/// Derives the generators with the given indices from the seed.
+fn derive_generators(seed: &[u8; 32], indices: Range<usize>) -> Vec<G1Projective> {
+    #[cfg(feature = "parallel")]
+    let generators = indices.into_par_iter().map(|i| generator(seed, i as u64)).collect();
+
+    #[cfg(not(feature = "parallel"))]
+    let generators = indices.map(|i| generator(seed, i as u64)).collect();
+
+    generators
+}
+
+/// Derives the generator with the given index from the seed. The index is always encoded as a
+/// little-endian `u64`, independent of the platform's pointer width.
+fn generator(seed: &[u8; 32], index: u64) -> G1Projective {
+    let mut msg = [0u8; 40];
+    msg[..32].copy_from_slice(seed);
+    msg[32..].copy_from_slice(&index.to_le_bytes());
+
+    G1Projective::hash_to_curve(&msg, DST, &[])
+}
+
+/// Computes the checksum of serialized generators, see [`PedersenCommitter::to_bytes`].
+fn checksum(seed: &[u8; 32], points: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update(DST).chain_update(seed).chain_update(points).finalize().into()
+}
+
+/// Samples a uniformly random scalar by rejection sampling.
+pub(crate) fn random_scalar<R: Rng>(rng: &mut R) -> Scalar {
+    loop {
+        let mut bytes = [0u8; 32];
+        rng.fill(&mut bytes);
+        // The modulus is smaller than 2^255, so clearing the top bit accepts ~90% of samples.
+        bytes[31] &= 0x7f;
+
+        if let Some(scalar) = Scalar::from_bytes_le(&bytes).into() {
+            return scalar;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_precomputed_commit_matches() {
+        let seed = [1u8; 32];
+        let n = 1024;
+
+        let symbols = (0..n).map(|_| random_scalar(&mut rand::rng())).collect::<Vec<_>>();
+        let committer = PedersenCommitter::new(seed, n);
+
+        let start = Instant::now();
+        let expected = committer.commit(&symbols);
+        println!("Commitment time: {:?}", start.elapsed());
+
+        for window_bits in [1, 4, 8] {
+            let start = Instant::now();
+            let precomputed = PedersenCommitter::with_precomputation(seed, n, window_bits);
+            println!(
+                "Precomputation time ({window_bits} bits, {}KiB): {:?}",
+                precomputed.precomputed_size() / 1024,
+                start.elapsed()
+            );
+
+            let start = Instant::now();
+            assert_eq!(precomputed.commit(&symbols), expected);
+            println!("Precomputed commitment time ({window_bits} bits): {:?}", start.elapsed());
+        }
+    }
+
+    #[test]
+    fn test_serialize_and_extend_generators() {
+        let seed = [2u8; 32];
+        let n = 256;
+
+        let start = Instant::now();
+        let committer = PedersenCommitter::new(seed, n);
+        println!("Derivation time ({n} generators): {:?}", start.elapsed());
+
+        let bytes = committer.to_bytes();
+        assert_eq!(bytes.len(), 72 + 48 * n);
+
+        let start = Instant::now();
+        let loaded = PedersenCommitter::from_bytes(&seed, &bytes).unwrap();
+        println!("Loading time ({n} generators): {:?}", start.elapsed());
+        assert_eq!(loaded.generators, committer.generators);
+
+        // Other seeds, corrupted points and truncated encodings are rejected.
+        assert!(PedersenCommitter::from_bytes(&[3u8; 32], &bytes).is_err());
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 1;
+        assert!(PedersenCommitter::from_bytes(&seed, &corrupted).is_err());
+        assert!(PedersenCommitter::from_bytes(&seed, &bytes[..bytes.len() - 48]).is_err());
+        let mut huge = bytes;
+        huge[32..40].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            PedersenCommitter::from_bytes(&seed, &huge),
+            Err(AuthError::InvalidEncoding)
+        ));
+
+        // Extending derives the same generators as deriving them all at once.
+        let mut extended = PedersenCommitter::with_precomputation(seed, n / 2, 4);
+        extended.extend(n);
+        assert_eq!(extended.generators, committer.generators);
+
+        let symbols = (0..n).map(|_| random_scalar(&mut rand::rng())).collect::<Vec<_>>();
+        assert_eq!(extended.commit(&symbols), committer.commit(&symbols));
+    }
+
+    /// Known-answer tests for the generator derivation, which must produce the same generators on
+    /// every platform. Changing these values requires bumping [`DST`].
+    #[test]
+    fn test_generator_known_answers() {
+        let hex = |point: &G1Projective| {
+            point.to_affine().to_compressed().iter().map(|b| format!("{b:02x}")).collect::<String>()
+        };
+
+        let cases = [
+            (
+                [0u8; 32],
+                0,
+                "826efedefeba82b85c23e32980f5fd752e03b69573e36c49e0ccfc8ba6247148c65bcd744ac2f00325d23cbb556d9cb0",
+            ),
+            (
+                [0u8; 32],
+                1,
+                "b95751bc2354740cb0a7673f5394d9846c31dbbf8391b0809f0f803e46f75d3fde169750f7a64043a2832f964114c5d8",
+            ),
+            (
+                [0xab; 32],
+                0,
+                "83bb6508c008f46fec005564a737d5465c8e5cec6b6539c884ecc23296f3dc021d8ebf2e0f644591c8b12ddff4f34eb4",
+            ),
+            (
+                [0xab; 32],
+                1,
+                "b5b668990b304ba4b6063baee3074dc599aed3285ec8a8fcca97fd7d8f1dc732483ea60d2d5b262ad19cfb3d7b31d726",
+            ),
+            (
+                [0xab; 32],
+                2,
+                "8c16d66ce8e9da8156d1fe1c2477a0749c3eb83bfc79aaa2820f9984520aa23124dfa623f7fbd078595a8974999c4e69",
+            ),
+        ];
+
+        for (seed, index, expected) in cases {
+            assert_eq!(hex(&generator(&seed, index)), expected);
+            assert_eq!(
+                hex(&PedersenCommitter::new(seed, index as usize + 1).generators[index as usize]),
+                expected
+            );
+        }
+
+        let committer = PedersenCommitter::new([0xab; 32], 3);
+        let symbols = [Scalar::from(1u64), Scalar::from(2u64), Scalar::from(3u64)];
+        assert_eq!(
+            hex(&committer.commit(&symbols)),
+            "b9a1e3fb64ba7641b96c92c79c2ddfaddcc9e35d8e4560bca71532ea73305d5aadbde33b3c8a5833d839b9924025a70c"
+        );
     }
 }
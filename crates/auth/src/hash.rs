@@ -0,0 +1,359 @@
+//! This module implements the homomorphic hash function of Krohn, Freedman and Mazières
+//! ("On-the-Fly Verification of Rateless Erasure Codes for Efficient Content Distribution").
+use blstrs::Scalar;
+use crypto_bigint::{
+    Encoding, MultiExponentiateBoundedExp, NonZero, Odd, U256, U2048,
+    modular::{MontyForm, MontyParams},
+};
+use group::ff::{Field, PrimeField};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use zc_rlnc::primitives::{Chunks, packet::RLNCPacket};
+
+use crate::{batch::random_weight, verify::AuthError};
+
+/// The 2048-bit prime modulus `p = k · r + 1`, where `r` is the order of the BLS12-381 scalar
+/// field. It was found by searching upwards from `k` derived from `SHA-256("ZC_KFM_P2048" || i)`.
+const MODULUS: U2048 = U2048::from_be_hex(concat!(
+    "a2dc08c1748a61261549bed0f5a4ffd72258607fb00b18addea5535c7f94b615",
+    "a3c9f4715a4b9ee3f6cbccdefd56851c5f8736a68655de5129dd18f9114d2467",
+    "175af09eb53bbf11e1425f205802a29beb5bd6f649e14569e792ab7733d51976",
+    "68d00f456bf152c7b54d86fe4dd457d1b64bf4f957b8a297cf0ddbe6227e6b8b",
+    "0abdb556f4807d1df2c907d4fa377f1334def64bc87791204562ff0c90e93b47",
+    "7d986a5f263001d6a99b5e1b696a6ddf4e1de070670a83bd1472437f2b5f9d33",
+    "92e64a45f4c71ad272698d7068994a27bdc4f3a301034386df5039065b8e42e9",
+    "06e35aab92b0807b87539b3bbeefe6011526b0966b47a50c0ce9fd2d16d1e16b",
+));
+
+/// The cofactor `k = (p - 1) / r`. Raising any element to the cofactor maps it into the subgroup
+/// of order `r`.
+const COFACTOR: U2048 = U2048::from_be_hex(concat!(
+    "0000000000000000000000000000000000000000000000000000000000000001",
+    "67a2e2d44f9174ec23165bc7672d4d223f44358c697bbcf8331e46bcc283d88c",
+    "8cd08e3e84933732ee698d125328c79f7a486ec009b9e7474a63dc99d562371f",
+    "de9042278019a7769209ad78f8b8ec947207c017fbbe0a734854e39e2ead3be3",
+    "3fa0cdf058f239b692ecbcf049539a1a6c1d44e7b12e0c086650d755a894c1d8",
+    "40586dc25759c61910460adb792b029c9e2634584ee072311161243e8aafe20f",
+    "7218eb309828d6955be36fb50d81d8bc0e3728c557c6c6b54d0ccc9e7ad88fed",
+    "2a4a6944ad37f09e8aa8c2ca02e7d09ceb9a316cfba74d0d23bbde9716d1e16a",
+));
+
+/// The number of bits of the cofactor.
+const COFACTOR_BITS: u32 = 1793;
+
+/// The number of bits of the scalar field modulus, which bounds all exponents.
+const SCALAR_BITS: u32 = 255;
+
+/// The domain separation tag for deriving the generators.
+const DST: &[u8] = b"RLNC_KFM_GEN";
+
+/// A homomorphic hash, i.e. an element of the order `r` subgroup of `Z_p^*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HomomorphicHash(MontyForm<{ U2048::LIMBS }>);
+
+impl HomomorphicHash {
+    /// Decodes a big-endian encoded hash, checking that it's in the subgroup of order `r`.
+    pub fn from_bytes(bytes: &[u8; 256]) -> Result<Self, AuthError> {
+        let value = U2048::from_be_slice(bytes);
+        if value >= MODULUS {
+            return Err(AuthError::InvalidEncoding);
+        }
+
+        let hash = MontyForm::new(&value, params());
+        let one = MontyForm::one(params());
+        if hash == MontyForm::zero(params()) || hash.pow_bounded_exp(&order(), SCALAR_BITS) != one {
+            return Err(AuthError::InvalidEncoding);
+        }
+
+        Ok(Self(hash))
+    }
+
+    /// Returns the big-endian encoded hash.
+    pub const fn to_bytes(&self) -> [u8; 256] {
+        self.0.retrieve().to_be_bytes()
+    }
+
+    /// Combines hashes with the given coefficients, i.e. computes `Πᵢ hᵢ^cᵢ`. This is the hash of
+    /// the same linear combination of the hashed symbols.
+    pub fn combine(hashes: &[Self], coefficients: &[Scalar]) -> Self {
+        let terms =
+            hashes.iter().zip(coefficients).map(|(h, c)| (h.0, exponent(c))).collect::<Vec<_>>();
+
+        Self(MontyForm::multi_exponentiate_bounded_exp(terms.as_slice(), SCALAR_BITS))
+    }
+}
+
+/// A hasher that uses the homomorphic hash function of Krohn, Freedman and Mazières (KFM).
+///
+/// # Idea
+/// Like [`PedersenCommitter`](crate::commit::PedersenCommitter), the hash of symbols `x` is a
+/// product of powers of generators
+///
+/// ```text
+/// hash(x) = Πⱼ gⱼ^xⱼ (mod p)
+/// ```
+///
+/// but in the order `r` subgroup of the multiplicative group of integers modulo a 2048-bit prime
+/// `p`, instead of on an elliptic curve. The group order `r` is matched to the BLS12-381 scalar
+/// field used by the RLNC [`Field`](zc_rlnc::primitives::field::Field), so linear combinations
+/// of symbols and coding coefficients map directly to exponents, and
+///
+/// ```text
+/// hash(Σᵢ cᵢ · xᵢ) = Πᵢ hash(xᵢ)^cᵢ
+/// ```
+///
+/// # Performance
+/// Hashing is considerably more expensive than Pedersen commitments, since there's no equivalent
+/// of fast multi-scalar multiplication on elliptic curves and the group elements are much larger.
+/// Verification should therefore use [`HashVerifier::verify_batch`], which amortizes a single
+/// hash over many packets.
+#[derive(Debug)]
+pub struct HomomorphicHasher {
+    generators: Vec<MontyForm<{ U2048::LIMBS }>>,
+}
+
+impl HomomorphicHasher {
+    /// Creates a new deterministic hasher with the given seed and number of generators.
+    pub fn new(seed: [u8; 32], n: usize) -> Self {
+        #[cfg(feature = "parallel")]
+        let generators = {
+            use rayon::prelude::*;
+            (0..n).into_par_iter().map(|i| generator(&seed, i)).collect()
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let generators = (0..n).map(|i| generator(&seed, i)).collect();
+
+        Self { generators }
+    }
+
+    /// Returns the number of generators (i.e. the number of symbols that can be hashed).
+    pub const fn len(&self) -> usize {
+        self.generators.len()
+    }
+
+    /// Returns true if the hasher has no generators.
+    pub const fn is_empty(&self) -> bool {
+        self.generators.is_empty()
+    }
+
+    /// Hashes the symbols.
+    pub fn hash(&self, symbols: &[Scalar]) -> HomomorphicHash {
+        assert_eq!(symbols.len(), self.generators.len());
+
+        let terms = self
+            .generators
+            .iter()
+            .zip(symbols)
+            .filter(|(_, s)| !bool::from(s.is_zero()))
+            .map(|(g, s)| (*g, exponent(s)))
+            .collect::<Vec<_>>();
+
+        HomomorphicHash(MontyForm::multi_exponentiate_bounded_exp(terms.as_slice(), SCALAR_BITS))
+    }
+
+    /// Hashes every chunk. With the `parallel` feature, chunks are hashed on different threads.
+    pub fn hash_chunks(&self, chunks: &Chunks<Scalar>) -> Result<Vec<HomomorphicHash>, AuthError> {
+        let symbols = chunks.layout().symbols_per_chunk;
+        if symbols != self.len() {
+            return Err(AuthError::InvalidSymbolCount(symbols, self.len()));
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            Ok(chunks.inner().par_iter().map(|c| self.hash(c.symbols())).collect())
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        Ok(chunks.inner().iter().map(|c| self.hash(c.symbols())).collect())
+    }
+}
+
+/// Verifies coded packets against the homomorphic hashes of the original chunks.
+///
+/// A coded packet with coding vector `c` and data `d` is valid if
+///
+/// ```text
+/// Πᵢ Hᵢ^cᵢ == hash(d)
+/// ```
+///
+/// where `Hᵢ` are the chunk hashes.
+#[derive(Debug)]
+pub struct HashVerifier {
+    hasher: HomomorphicHasher,
+    hashes: Vec<HomomorphicHash>,
+}
+
+impl HashVerifier {
+    /// Creates a new verifier from the hasher and the hashes of the original chunks.
+    pub const fn new(hasher: HomomorphicHasher, hashes: Vec<HomomorphicHash>) -> Self {
+        Self { hasher, hashes }
+    }
+
+    /// Verifies a single coded packet.
+    pub fn verify(&self, packet: &RLNCPacket<Scalar>) -> Result<(), AuthError> {
+        self.check_lengths(packet)?;
+
+        if HomomorphicHash::combine(&self.hashes, &packet.coding_vector) !=
+            self.hasher.hash(&packet.data)
+        {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies many coded packets at once, using `rng` to sample random 128-bit weights `rⱼ`
+    /// and checking
+    ///
+    /// ```text
+    /// Πᵢ Hᵢ^(Σⱼ rⱼ · cⱼᵢ) == hash(Σⱼ rⱼ · dⱼ)
+    /// ```
+    ///
+    /// as proposed by KFM. This costs a single hash and combination for the whole batch. If the
+    /// batch fails, the packets are verified individually, and [`AuthError::InvalidPackets`] is
+    /// returned with the indices of all invalid packets.
+    pub fn verify_batch<R: Rng>(
+        &self,
+        packets: &[RLNCPacket<Scalar>],
+        mut rng: R,
+    ) -> Result<(), AuthError> {
+        let mut coefficients = vec![Scalar::ZERO; self.hashes.len()];
+        let mut data = vec![Scalar::ZERO; self.hasher.len()];
+
+        let mut valid = packets.iter().all(|p| self.check_lengths(p).is_ok());
+        if valid {
+            for packet in packets {
+                let weight = random_weight(&mut rng);
+
+                for (acc, c) in coefficients.iter_mut().zip(&packet.coding_vector) {
+                    *acc += weight * c;
+                }
+
+                for (acc, d) in data.iter_mut().zip(&packet.data) {
+                    *acc += weight * d;
+                }
+            }
+
+            valid =
+                HomomorphicHash::combine(&self.hashes, &coefficients) == self.hasher.hash(&data);
+        }
+
+        if valid {
+            return Ok(());
+        }
+
+        let invalid = packets
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| self.verify(p).is_err())
+            .map(|(i, _)| i)
+            .collect();
+
+        Err(AuthError::InvalidPackets(invalid))
+    }
+
+    /// Checks that the coding vector and data lengths match the hashes and generators.
+    const fn check_lengths(&self, packet: &RLNCPacket<Scalar>) -> Result<(), AuthError> {
+        if packet.coding_vector.len() != self.hashes.len() {
+            return Err(AuthError::InvalidCodingVectorLength(
+                packet.coding_vector.len(),
+                self.hashes.len(),
+            ));
+        }
+
+        if packet.data.len() != self.hasher.len() {
+            return Err(AuthError::InvalidSymbolCount(packet.data.len(), self.hasher.len()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the Montgomery parameters for the modulus.
+fn params() -> MontyParams<{ U2048::LIMBS }> {
+    MontyParams::new_vartime(Odd::new(MODULUS).expect("modulus is odd"))
+}
+
+/// Returns the order `r` of the subgroup, i.e. the BLS12-381 scalar field modulus.
+fn order() -> U256 {
+    U256::from_be_hex(Scalar::MODULUS.trim_start_matches("0x"))
+}
+
+/// Converts a scalar into an exponent.
+fn exponent(scalar: &Scalar) -> U256 {
+    U256::from_le_bytes(scalar.to_bytes_le())
+}
+
+/// Derives the generator at `index` from the seed, by expanding `(seed, index)` into 2048 bits
+/// with SHA-256, reducing modulo `p`, and raising to the cofactor.
+fn generator(seed: &[u8; 32], index: usize) -> MontyForm<{ U2048::LIMBS }> {
+    let modulus = NonZero::new(MODULUS).expect("modulus is non-zero");
+
+    let mut attempt = 0u64;
+    loop {
+        let mut bytes = [0u8; 256];
+        for (block, out) in bytes.chunks_exact_mut(32).enumerate() {
+            let digest = Sha256::new()
+                .chain_update(DST)
+                .chain_update(seed)
+                .chain_update((index as u64).to_le_bytes())
+                .chain_update(attempt.to_le_bytes())
+                .chain_update([block as u8])
+                .finalize();
+            out.copy_from_slice(&digest);
+        }
+
+        let value = U2048::from_be_slice(&bytes).rem(&modulus);
+        let generator = MontyForm::new(&value, params()).pow_bounded_exp(&COFACTOR, COFACTOR_BITS);
+
+        if generator != MontyForm::one(params()) && generator != MontyForm::zero(params()) {
+            return generator;
+        }
+
+        // Only happens with negligible probability.
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use zc_rlnc::encode::Encoder;
+
+    use super::*;
+
+    #[test]
+    fn test_homomorphic_hash_verify() {
+        let data = rand::rng().random_iter().take(1024 * 4).collect::<Vec<_>>();
+        let chunk_count = 4;
+        let packet_count = 8;
+
+        let chunks = Encoder::<Scalar>::prepare(&data, chunk_count).unwrap();
+        let hasher = HomomorphicHasher::new([0u8; 32], chunks.layout().symbols_per_chunk);
+        let hashes = hasher.hash_chunks(&chunks).unwrap();
+
+        let hash = HomomorphicHash::from_bytes(&hashes[0].to_bytes()).unwrap();
+        assert_eq!(hash, hashes[0]);
+        assert!(HomomorphicHash::from_bytes(&[0xff; 256]).is_err());
+
+        let encoder = Encoder::from_chunks(chunks);
+        let mut packets = encoder.encode_batch(rand::rng(), packet_count).unwrap();
+        let verifier = HashVerifier::new(hasher, hashes);
+
+        for packet in &packets {
+            verifier.verify(packet).unwrap();
+        }
+        verifier.verify_batch(&packets, rand::rng()).unwrap();
+
+        // Pollute some packets.
+        packets[2].data[0] += Scalar::ONE;
+        packets[5].coding_vector[3] += Scalar::ONE;
+        assert!(matches!(verifier.verify(&packets[2]), Err(AuthError::InvalidPacket)));
+        assert!(matches!(
+            verifier.verify_batch(&packets, rand::rng()),
+            Err(AuthError::InvalidPackets(indices)) if indices == [2, 5]
+        ));
+    }
+}
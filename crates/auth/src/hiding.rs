@@ -0,0 +1,309 @@
+//! This module implements hiding Pedersen commitments, with blinding factors that are combined
+//! along with the coding vector.
+use blstrs::{G1Projective, Scalar};
+use group::ff::Field;
+use rand::Rng;
+use zc_rlnc::{
+    RLNCError,
+    encode::Encoder,
+    primitives::{ChunkLayout, Chunks, packet::RLNCPacket},
+};
+
+use crate::{
+    commit::{ChunkCommitments, PedersenCommitter, random_scalar},
+    verify::AuthError,
+};
+
+/// The domain separation tag for the blinding generator. It's distinct from the tag of the
+/// symbol generators, so nobody knows the discrete logarithm of one with respect to the others.
+const BLINDING_DST: &[u8] = b"RLNC_PEDERSEN_BLINDING_GEN";
+
+/// A committer that uses the hiding Pedersen commitment scheme.
+///
+/// # Idea
+/// Next to the generators of [`PedersenCommitter`], there's an extra blinding generator `h`. A
+/// commitment to symbols `x` with a random blinding factor `r` is
+///
+/// ```text
+/// commit(x, r) = Σⱼ xⱼ · Gⱼ + r · H
+/// ```
+///
+/// which reveals nothing about `x` as long as `r` is secret and uniformly random. The commitment
+/// is still additively homomorphic in both the symbols and the blinding factor, so a coded packet
+/// remains verifiable if it carries the same linear combination of the chunks' blinding factors,
+/// see [`BlindedPacket`].
+#[derive(Debug)]
+pub struct HidingCommitter {
+    committer: PedersenCommitter,
+    blinding: G1Projective,
+}
+
+impl HidingCommitter {
+    /// Creates a new deterministic hiding committer with the given seed and number of
+    /// generators.
+    pub fn new(seed: [u8; 32], n: usize) -> Self {
+        Self::from_committer(PedersenCommitter::new(seed, n))
+    }
+
+    /// Creates a new hiding committer from an existing committer (e.g. to reuse precomputed
+    /// tables). The blinding generator is derived from the committer's seed.
+    pub fn from_committer(committer: PedersenCommitter) -> Self {
+        let blinding = G1Projective::hash_to_curve(committer.seed(), BLINDING_DST, &[]);
+        Self { committer, blinding }
+    }
+
+    /// Returns the underlying (non-hiding) committer.
+    pub const fn committer(&self) -> &PedersenCommitter {
+        &self.committer
+    }
+
+    /// Returns the number of generators (i.e. the number of symbols that can be committed to).
+    pub const fn len(&self) -> usize {
+        self.committer.len()
+    }
+
+    /// Returns true if the committer has no generators.
+    pub const fn is_empty(&self) -> bool {
+        self.committer.is_empty()
+    }
+
+    /// Commits to the symbols with the given blinding factor.
+    pub fn commit(&self, symbols: &[Scalar], blinding: &Scalar) -> G1Projective {
+        self.committer.commit(symbols) + self.blinding * blinding
+    }
+}
+
+/// A coded packet together with the blinding factor of its commitment, i.e. the linear
+/// combination of the chunks' blinding factors with the packet's coding vector.
+#[derive(Debug, Clone)]
+pub struct BlindedPacket {
+    /// The coded packet.
+    pub packet: RLNCPacket<Scalar>,
+    /// The combined blinding factor.
+    pub blinding: Scalar,
+}
+
+impl BlindedPacket {
+    /// Recodes the packets into a new packet with a random linear combination, using the
+    /// provided random number generator. The blinding factors are combined with the same
+    /// coefficients, so the recoded packet verifies against the original commitments.
+    pub fn recode<R: Rng>(packets: &[Self], mut rng: R) -> Result<Self, AuthError> {
+        let Some(first) = packets.first() else {
+            return Err(RLNCError::NotEnoughPackets(0, 1).into());
+        };
+
+        let (chunk_count, symbol_count) =
+            (first.packet.coding_vector.len(), first.packet.data.len());
+
+        let mut coding_vector = vec![Scalar::ZERO; chunk_count];
+        let mut data = vec![Scalar::ZERO; symbol_count];
+        let mut blinding = Scalar::ZERO;
+
+        for Self { packet, blinding: b } in packets {
+            if packet.coding_vector.len() != chunk_count {
+                return Err(AuthError::InvalidCodingVectorLength(
+                    packet.coding_vector.len(),
+                    chunk_count,
+                ));
+            }
+
+            if packet.data.len() != symbol_count {
+                return Err(AuthError::InvalidSymbolCount(packet.data.len(), symbol_count));
+            }
+
+            let coefficient = random_scalar(&mut rng);
+
+            for (acc, c) in coding_vector.iter_mut().zip(&packet.coding_vector) {
+                *acc += coefficient * c;
+            }
+
+            for (acc, d) in data.iter_mut().zip(&packet.data) {
+                *acc += coefficient * d;
+            }
+
+            blinding += coefficient * b;
+        }
+
+        Ok(Self { packet: RLNCPacket { coding_vector, data }, blinding })
+    }
+}
+
+/// An encoder that commits to the chunks of the data with hiding commitments, and produces
+/// [`BlindedPacket`]s that verify against them.
+#[derive(Debug)]
+pub struct HidingEncoder {
+    encoder: Encoder<Scalar>,
+    blindings: Vec<Scalar>,
+    commitments: ChunkCommitments,
+}
+
+impl HidingEncoder {
+    /// Creates a new hiding encoder for the given data and chunk count. The generators are
+    /// derived from `seed`, and the blinding factors of the chunks are sampled from `rng`.
+    pub fn new<R: Rng>(
+        data: impl AsRef<[u8]>,
+        chunk_count: usize,
+        seed: [u8; 32],
+        rng: R,
+    ) -> Result<Self, AuthError> {
+        let chunks = Encoder::prepare(data, chunk_count)?;
+        let committer = HidingCommitter::new(seed, chunks.layout().symbols_per_chunk);
+
+        Self::from_chunks(chunks, &committer, rng)
+    }
+
+    /// Creates a new hiding encoder from prepared chunks and an existing committer, sampling
+    /// the blinding factors of the chunks from `rng`.
+    pub fn from_chunks<R: Rng>(
+        chunks: Chunks<Scalar>,
+        committer: &HidingCommitter,
+        mut rng: R,
+    ) -> Result<Self, AuthError> {
+        let symbols = chunks.layout().symbols_per_chunk;
+        if symbols != committer.len() {
+            return Err(AuthError::InvalidSymbolCount(symbols, committer.len()));
+        }
+
+        let blindings = (0..chunks.len()).map(|_| random_scalar(&mut rng)).collect::<Vec<_>>();
+        let commitments = chunks
+            .inner()
+            .iter()
+            .zip(&blindings)
+            .map(|(chunk, blinding)| committer.commit(chunk.symbols(), blinding))
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            encoder: Encoder::from_chunks(chunks),
+            blindings,
+            commitments: commitments.into(),
+        })
+    }
+
+    /// Returns the hiding commitments to the chunks.
+    pub const fn commitments(&self) -> &ChunkCommitments {
+        &self.commitments
+    }
+
+    /// Returns the layout of the chunks and the resulting coded packets.
+    pub const fn layout(&self) -> ChunkLayout {
+        self.encoder.layout()
+    }
+
+    /// Returns the underlying encoder.
+    pub const fn encoder(&self) -> &Encoder<Scalar> {
+        &self.encoder
+    }
+
+    /// Encodes the data with the given coding vector, and combines the blinding factors with it.
+    pub fn encode_with_vector(&self, coding_vector: &[Scalar]) -> Result<BlindedPacket, AuthError> {
+        let packet = self.encoder.encode_with_vector(coding_vector)?;
+        let blinding = coding_vector.iter().zip(&self.blindings).map(|(c, r)| c * r).sum();
+
+        Ok(BlindedPacket { packet, blinding })
+    }
+
+    /// Encodes the data with a random coding vector, using the provided random number generator.
+    pub fn encode<R: Rng>(&self, mut rng: R) -> Result<BlindedPacket, AuthError> {
+        let coding_vector =
+            (0..self.blindings.len()).map(|_| random_scalar(&mut rng)).collect::<Vec<_>>();
+
+        self.encode_with_vector(&coding_vector)
+    }
+}
+
+/// Verifies [`BlindedPacket`]s against the hiding commitments of the original chunks.
+///
+/// A blinded packet with coding vector `c`, data `d` and blinding factor `ρ` is valid if
+///
+/// ```text
+/// Σᵢ cᵢ · Cᵢ == commit(d, ρ)
+/// ```
+///
+/// where `Cᵢ` are the chunk commitments.
+#[derive(Debug)]
+pub struct HidingVerifier {
+    committer: HidingCommitter,
+    commitments: ChunkCommitments,
+}
+
+impl HidingVerifier {
+    /// Creates a new verifier from the committer and the commitments of the original chunks.
+    pub const fn new(committer: HidingCommitter, commitments: ChunkCommitments) -> Self {
+        Self { committer, commitments }
+    }
+
+    /// Verifies a single blinded packet.
+    pub fn verify(&self, packet: &BlindedPacket) -> Result<(), AuthError> {
+        let BlindedPacket { packet, blinding } = packet;
+
+        if packet.coding_vector.len() != self.commitments.len() {
+            return Err(AuthError::InvalidCodingVectorLength(
+                packet.coding_vector.len(),
+                self.commitments.len(),
+            ));
+        }
+
+        if packet.data.len() != self.committer.len() {
+            return Err(AuthError::InvalidSymbolCount(packet.data.len(), self.committer.len()));
+        }
+
+        let expected = G1Projective::multi_exp(self.commitments.inner(), &packet.coding_vector);
+        if expected != self.committer.commit(&packet.data, blinding) {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use zc_rlnc::decode::Decoder;
+
+    use super::*;
+
+    #[test]
+    fn test_hiding_encode_recode_decode() {
+        let data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let chunk_count = 4;
+        let seed = [5u8; 32];
+
+        let encoder = HidingEncoder::new(&data, chunk_count, seed, rand::rng()).unwrap();
+
+        // Committing to the same data twice yields different commitments.
+        let other = HidingEncoder::new(&data, chunk_count, seed, rand::rng()).unwrap();
+        assert_ne!(encoder.commitments(), other.commitments());
+
+        // The blinding generator is derived from the seed of the reused committer.
+        let committer = PedersenCommitter::new(seed, encoder.layout().symbols_per_chunk);
+        let committer = HidingCommitter::from_committer(committer);
+        let verifier = HidingVerifier::new(committer, encoder.commitments().clone());
+
+        let packets = (0..3).map(|_| encoder.encode(rand::rng()).unwrap()).collect::<Vec<_>>();
+        for packet in &packets {
+            verifier.verify(packet).unwrap();
+        }
+
+        // A packet with a wrong blinding factor is rejected.
+        let mut polluted = packets[0].clone();
+        polluted.blinding += Scalar::ONE;
+        assert!(matches!(verifier.verify(&polluted), Err(AuthError::InvalidPacket)));
+
+        // Packets verified by a relay are recoded, and still verify against the commitments.
+        let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+        let decoded = loop {
+            let mut relayed = packets.clone();
+            relayed.push(encoder.encode(rand::rng()).unwrap());
+
+            let recoded = BlindedPacket::recode(&relayed, rand::rng()).unwrap();
+            verifier.verify(&recoded).unwrap();
+
+            if let Some(decoded) = decoder.decode(recoded.packet).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,375 @@
+//! This module implements homomorphic network coding signatures (Boneh–Freeman–Katz–Waters,
+//! "Signing a Linear Subspace").
+use blstrs::{Bls12, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar};
+use group::{Curve, Group, ff::Field, prime::PrimeCurveAffine};
+use pairing::{MillerLoopResult, MultiMillerLoop};
+use rand::Rng;
+use zc_rlnc::{
+    RLNCError,
+    encode::Encoder,
+    primitives::{ChunkLayout, Chunks, packet::RLNCPacket},
+};
+
+use crate::{
+    commit::{PedersenCommitter, random_scalar},
+    sign::SecretKey,
+    verify::AuthError,
+};
+
+/// The domain separation tag for hashing a generation id and chunk index to G1.
+const DST: &[u8] = b"RLNC_BFKW_GENERATION";
+
+/// A secret key for signing generations with homomorphic signatures.
+#[derive(Clone)]
+pub struct SigningKey(Scalar);
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SigningKey").finish_non_exhaustive()
+    }
+}
+
+impl From<SecretKey> for SigningKey {
+    fn from(key: SecretKey) -> Self {
+        Self(*key.scalar())
+    }
+}
+
+impl SigningKey {
+    /// Generates a new random signing key.
+    pub fn random<R: Rng>(rng: R) -> Self {
+        SecretKey::random(rng).into()
+    }
+
+    /// Returns the verifying key of this signing key.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey(G2Projective::generator() * self.0)
+    }
+
+    /// Signs the chunks of a generation, i.e. the augmented basis vectors `(eᵢ, xᵢ)` of the
+    /// subspace spanned by the source packets. Returns one signature per chunk.
+    ///
+    /// The generation id must be unique for every generation signed with this key, otherwise
+    /// packets of the two generations can be combined into valid signatures over data that's in
+    /// neither.
+    pub fn sign_generation(
+        &self,
+        committer: &PedersenCommitter,
+        id: &[u8; 32],
+        chunks: &Chunks<Scalar>,
+    ) -> Result<Vec<PacketSignature>, AuthError> {
+        let symbols = chunks.layout().symbols_per_chunk;
+        if symbols != committer.len() {
+            return Err(AuthError::InvalidSymbolCount(symbols, committer.len()));
+        }
+
+        Ok(chunks
+            .inner()
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                PacketSignature((hash_index(id, i) + committer.commit(chunk.symbols())) * self.0)
+            })
+            .collect())
+    }
+}
+
+/// A public key for verifying homomorphic signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyingKey(G2Projective);
+
+impl VerifyingKey {
+    /// Decodes a compressed verifying key, rejecting the identity.
+    pub fn from_bytes(bytes: &[u8; 96]) -> Result<Self, AuthError> {
+        Option::<G2Affine>::from(G2Affine::from_compressed(bytes))
+            .filter(|p| !bool::from(p.is_identity()))
+            .map(|p| Self(p.into()))
+            .ok_or(AuthError::InvalidEncoding)
+    }
+
+    /// Returns the compressed verifying key.
+    pub fn to_bytes(&self) -> [u8; 96] {
+        self.0.to_affine().to_compressed()
+    }
+}
+
+/// A homomorphic signature over the augmented vector `(c, d)` of a coded packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketSignature(G1Projective);
+
+impl PacketSignature {
+    /// Decodes a compressed signature.
+    pub fn from_bytes(bytes: &[u8; 48]) -> Result<Self, AuthError> {
+        Option::<G1Affine>::from(G1Affine::from_compressed(bytes))
+            .map(|p| Self(p.into()))
+            .ok_or(AuthError::InvalidEncoding)
+    }
+
+    /// Returns the compressed signature.
+    pub fn to_bytes(&self) -> [u8; 48] {
+        self.0.to_affine().to_compressed()
+    }
+}
+
+/// A coded packet together with a homomorphic signature over its augmented vector.
+#[derive(Debug, Clone)]
+pub struct SignedPacket {
+    /// The coded packet.
+    pub packet: RLNCPacket<Scalar>,
+    /// The signature over the coding vector and data.
+    pub signature: PacketSignature,
+}
+
+impl SignedPacket {
+    /// Recodes the packets into a new packet with a random linear combination, using the
+    /// provided random number generator. The signatures are combined with the same
+    /// coefficients, so the recoded packet is signed without access to the signing key.
+    pub fn recode<R: Rng>(packets: &[Self], mut rng: R) -> Result<Self, AuthError> {
+        let Some(first) = packets.first() else {
+            return Err(RLNCError::NotEnoughPackets(0, 1).into());
+        };
+
+        let (chunk_count, symbol_count) =
+            (first.packet.coding_vector.len(), first.packet.data.len());
+
+        let mut coding_vector = vec![Scalar::ZERO; chunk_count];
+        let mut data = vec![Scalar::ZERO; symbol_count];
+        let mut signature = G1Projective::identity();
+
+        for Self { packet, signature: s } in packets {
+            if packet.coding_vector.len() != chunk_count {
+                return Err(AuthError::InvalidCodingVectorLength(
+                    packet.coding_vector.len(),
+                    chunk_count,
+                ));
+            }
+
+            if packet.data.len() != symbol_count {
+                return Err(AuthError::InvalidSymbolCount(packet.data.len(), symbol_count));
+            }
+
+            let coefficient = random_scalar(&mut rng);
+
+            for (acc, c) in coding_vector.iter_mut().zip(&packet.coding_vector) {
+                *acc += coefficient * c;
+            }
+
+            for (acc, d) in data.iter_mut().zip(&packet.data) {
+                *acc += coefficient * d;
+            }
+
+            signature += s.0 * coefficient;
+        }
+
+        Ok(Self {
+            packet: RLNCPacket { coding_vector, data },
+            signature: PacketSignature(signature),
+        })
+    }
+}
+
+/// An encoder that signs the chunks of a generation with homomorphic signatures, and produces
+/// [`SignedPacket`]s.
+#[derive(Debug)]
+pub struct HomomorphicEncoder {
+    encoder: Encoder<Scalar>,
+    signatures: Vec<PacketSignature>,
+}
+
+impl HomomorphicEncoder {
+    /// Creates a new encoder for the given data and chunk count, signing the generation `id` with
+    /// `key`. The generators are derived from `seed`.
+    pub fn new(
+        data: impl AsRef<[u8]>,
+        chunk_count: usize,
+        seed: [u8; 32],
+        id: &[u8; 32],
+        key: &SigningKey,
+    ) -> Result<Self, AuthError> {
+        let chunks = Encoder::prepare(data, chunk_count)?;
+        let committer = PedersenCommitter::new(seed, chunks.layout().symbols_per_chunk);
+
+        Self::from_chunks(chunks, &committer, id, key)
+    }
+
+    /// Creates a new encoder from prepared chunks and an existing committer.
+    pub fn from_chunks(
+        chunks: Chunks<Scalar>,
+        committer: &PedersenCommitter,
+        id: &[u8; 32],
+        key: &SigningKey,
+    ) -> Result<Self, AuthError> {
+        let signatures = key.sign_generation(committer, id, &chunks)?;
+        Ok(Self { encoder: Encoder::from_chunks(chunks), signatures })
+    }
+
+    /// Returns the layout of the chunks and the resulting coded packets.
+    pub const fn layout(&self) -> ChunkLayout {
+        self.encoder.layout()
+    }
+
+    /// Returns the underlying encoder.
+    pub const fn encoder(&self) -> &Encoder<Scalar> {
+        &self.encoder
+    }
+
+    /// Encodes the data with the given coding vector, and combines the chunk signatures with it.
+    pub fn encode_with_vector(&self, coding_vector: &[Scalar]) -> Result<SignedPacket, AuthError> {
+        let packet = self.encoder.encode_with_vector(coding_vector)?;
+
+        let signatures = self.signatures.iter().map(|s| s.0).collect::<Vec<_>>();
+        let signature = PacketSignature(G1Projective::multi_exp(&signatures, coding_vector));
+
+        Ok(SignedPacket { packet, signature })
+    }
+
+    /// Encodes the data with a random coding vector, using the provided random number generator.
+    pub fn encode<R: Rng>(&self, mut rng: R) -> Result<SignedPacket, AuthError> {
+        let coding_vector =
+            (0..self.signatures.len()).map(|_| random_scalar(&mut rng)).collect::<Vec<_>>();
+
+        self.encode_with_vector(&coding_vector)
+    }
+}
+
+/// Verifies [`SignedPacket`]s of a generation against the source's verifying key.
+///
+/// A signed packet with coding vector `c`, data `d` and signature `σ` is valid if
+///
+/// ```text
+/// e(σ, g₂) == e(Σᵢ cᵢ · H(id, i) + Σⱼ dⱼ · Gⱼ, pk)
+/// ```
+///
+/// where `H` hashes to G1, and `Gⱼ` are the generators of the [`PedersenCommitter`]. Unlike
+/// [`Verifier`](crate::verify::Verifier), this doesn't need any per-generation commitments: only
+/// the generation id and the source's key.
+#[derive(Debug)]
+pub struct HomomorphicVerifier {
+    key: VerifyingKey,
+    committer: PedersenCommitter,
+    hashes: Vec<G1Projective>,
+}
+
+impl HomomorphicVerifier {
+    /// Creates a new verifier for the generation `id` with `chunk_count` chunks.
+    pub fn new(
+        key: VerifyingKey,
+        committer: PedersenCommitter,
+        id: &[u8; 32],
+        chunk_count: usize,
+    ) -> Self {
+        let hashes = (0..chunk_count).map(|i| hash_index(id, i)).collect();
+        Self { key, committer, hashes }
+    }
+
+    /// Verifies a single signed packet.
+    pub fn verify(&self, packet: &SignedPacket) -> Result<(), AuthError> {
+        let SignedPacket { packet, signature } = packet;
+
+        if packet.coding_vector.len() != self.hashes.len() {
+            return Err(AuthError::InvalidCodingVectorLength(
+                packet.coding_vector.len(),
+                self.hashes.len(),
+            ));
+        }
+
+        if packet.data.len() != self.committer.len() {
+            return Err(AuthError::InvalidSymbolCount(packet.data.len(), self.committer.len()));
+        }
+
+        let message = G1Projective::multi_exp(&self.hashes, &packet.coding_vector) +
+            self.committer.commit(&packet.data);
+
+        // e(σ, g₂) == e(m, pk) <=> e(σ, -g₂) · e(m, pk) == 1
+        let generator = G2Prepared::from(-G2Affine::generator());
+        let key = G2Prepared::from(self.key.0.to_affine());
+
+        let result = Bls12::multi_miller_loop(&[
+            (&signature.0.to_affine(), &generator),
+            (&message.to_affine(), &key),
+        ])
+        .final_exponentiation();
+
+        if result != Gt::identity() {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+}
+
+/// Hashes the generation id and chunk index to G1.
+fn hash_index(id: &[u8; 32], index: usize) -> G1Projective {
+    let mut msg = [0u8; 40];
+    msg[..32].copy_from_slice(id);
+    msg[32..].copy_from_slice(&(index as u64).to_le_bytes());
+
+    G1Projective::hash_to_curve(&msg, DST, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::Rng;
+    use zc_rlnc::decode::Decoder;
+
+    use super::*;
+
+    #[test]
+    fn test_homomorphic_sign_recode_verify() {
+        let data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let chunk_count = 4;
+        let (seed, id) = ([1u8; 32], [2u8; 32]);
+
+        let key = SigningKey::random(rand::rng());
+        let verifying_key = VerifyingKey::from_bytes(&key.verifying_key().to_bytes()).unwrap();
+
+        let start = Instant::now();
+        let encoder = HomomorphicEncoder::new(&data, chunk_count, seed, &id, &key).unwrap();
+        println!("Signing time ({chunk_count} chunks): {:?}", start.elapsed());
+
+        let committer = PedersenCommitter::new(seed, encoder.layout().symbols_per_chunk);
+        let verifier = HomomorphicVerifier::new(verifying_key, committer, &id, chunk_count);
+
+        let packets = (0..2).map(|_| encoder.encode(rand::rng()).unwrap()).collect::<Vec<_>>();
+
+        let start = Instant::now();
+        verifier.verify(&packets[0]).unwrap();
+        println!("Verification time: {:?}", start.elapsed());
+
+        // Tampered packets are rejected.
+        let mut polluted = packets[0].clone();
+        polluted.packet.data[0] += Scalar::ONE;
+        assert!(matches!(verifier.verify(&polluted), Err(AuthError::InvalidPacket)));
+
+        let mut polluted = packets[0].clone();
+        polluted.packet.coding_vector[0] += Scalar::ONE;
+        assert!(matches!(verifier.verify(&polluted), Err(AuthError::InvalidPacket)));
+
+        // Packets of another generation signed by the same key can't be mixed in.
+        let other = HomomorphicEncoder::new(&data, chunk_count, seed, &[3u8; 32], &key).unwrap();
+        let foreign = other.encode(rand::rng()).unwrap();
+        assert!(verifier.verify(&foreign).is_err());
+        let mixed = SignedPacket::recode(&[packets[0].clone(), foreign], rand::rng()).unwrap();
+        assert!(verifier.verify(&mixed).is_err());
+
+        // Recoded packets verify without the signing key.
+        let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+        let decoded = loop {
+            let mut relayed = packets.clone();
+            relayed.push(encoder.encode(rand::rng()).unwrap());
+
+            let recoded = SignedPacket::recode(&relayed, rand::rng()).unwrap();
+            let signature = PacketSignature::from_bytes(&recoded.signature.to_bytes()).unwrap();
+            assert_eq!(signature, recoded.signature);
+            verifier.verify(&recoded).unwrap();
+
+            if let Some(decoded) = decoder.decode(recoded.packet).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,759 @@
+//! This module implements experimental lattice-based linearly homomorphic signatures over
+//! GF(2^8), in the style of Boneh–Freeman ("Linearly Homomorphic Signatures over Binary Fields
+//! and New Tools for Lattice-Based Signatures").
+//!
+//! Unlike the schemes over BLS12-381, their security doesn't rely on discrete logarithms, so they
+//! are conjectured to be post-quantum, and they're secure with small fields.
+//!
+//! # Warning
+//! This is an experiment to evaluate the approach, and must not be used in production:
+//! - The parameters haven't been vetted. The lattice dimension is the bit length of the packets,
+//!   which makes SIS hard for realistic packet sizes, but the dimensions used in tests are far too
+//!   small to be secure.
+//! - Keys and signatures are huge: a signature has `35 · 8` integers per bit of the packet. See
+//!   [`LatticeSignature`].
+//! - Sampling isn't constant time.
+use std::f64::consts::TAU;
+
+use group::ff::Field;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use zc_rlnc::{
+    RLNCError,
+    encode::Encoder,
+    primitives::{ChunkLayout, Chunks, field::Gf256, packet::RLNCPacket},
+};
+
+use crate::verify::AuthError;
+
+/// The domain separation tag for hashing a generation id to its lattice.
+const DST: &[u8] = b"RLNC_LATTICE_GENERATION";
+
+/// The number of bits of the modulus `2q`. With `q = 2^31`, arithmetic modulo `2q` is wrapping
+/// `u32` arithmetic.
+const LOG_MODULUS: usize = 32;
+
+/// The modulus `q`, which the signed bits are scaled by.
+const Q: u32 = 1 << 31;
+
+/// The standard deviation of the trapdoor entries.
+const SIGMA_TRAPDOOR: f64 = 3.2;
+
+/// The standard deviation of the gadget preimages.
+const SIGMA_GADGET: f64 = 6.0;
+
+/// The standard deviation of the randomized rounding of the perturbation.
+const SIGMA_ROUNDING: f64 = 3.0;
+
+/// The number of standard deviations at which the discrete Gaussians are cut off.
+const TAIL: f64 = 12.0;
+
+/// The maximum squared Euclidean norm of a valid signature, i.e. the norm is at most `2^30`.
+/// Fresh signatures have a norm around `2^17` for small dimensions, so this leaves room for a few
+/// hops of recoding, after which the norm grows too large.
+const NORM_BOUND: i128 = 1 << 60;
+
+/// The maximum absolute value of an entry of a valid signature, whose square alone reaches
+/// [`NORM_BOUND`]. Larger entries could wrap the norm computation around.
+const MAX_ENTRY: u64 = 1 << 30;
+
+/// The number of twists `αᵇ · v` signed per vector, see [`LatticeSignature`].
+const TWISTS: usize = 8;
+
+/// A secret key for signing generations with lattice-based homomorphic signatures.
+///
+/// # Idea
+/// The verifying key is a matrix `A = [I | Â | G - [I | Â]·R]` modulo `2q`, where `G` is the
+/// gadget matrix and `R` is a short trapdoor matrix (Micciancio–Peikert). For a generation id
+/// `τ`, the matrix is extended to `A_τ = [A | H(τ)]`. A signature on a binary vector `v` is a
+/// short integer vector `σ` with
+///
+/// ```text
+/// A_τ · σ = q · v   (mod 2q)
+/// ```
+///
+/// which is linear over GF(2), because `2q · x = 0 (mod 2q)`. Signatures are sampled from a
+/// discrete Gaussian with the trapdoor, and forging one on a vector outside the signed subspace
+/// requires solving SIS.
+pub struct LatticeSigningKey {
+    key: LatticeVerifyingKey,
+    /// The trapdoor `R`, with `2n` rows and `32n` columns.
+    trapdoor: Vec<i64>,
+    /// The Cholesky factor of the perturbation covariance, with `2n` rows and columns.
+    cholesky: Vec<f64>,
+    /// The standard deviation of the signatures.
+    sigma: f64,
+}
+
+impl std::fmt::Debug for LatticeSigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LatticeSigningKey").field("key", &self.key).finish_non_exhaustive()
+    }
+}
+
+impl LatticeSigningKey {
+    /// Generates a new random key for packets with the given layout. The lattice dimension `n`
+    /// is the bit length of the packets.
+    pub fn generate<R: Rng>(layout: ChunkLayout, mut rng: R) -> Self {
+        let n = dimension(layout);
+        let (width, gadget) = (2 * n, LOG_MODULUS * n);
+
+        let a_hat = (0..n * n).map(|_| rng.random()).collect::<Vec<u32>>();
+        let trapdoor = (0..width * gadget)
+            .map(|_| sample_z(&mut rng, 0.0, SIGMA_TRAPDOOR))
+            .collect::<Vec<i64>>();
+
+        // A_g = G - [I | Â] · R
+        let mut a_gadget = vec![0u32; n * gadget];
+        for row in 0..n {
+            for col in 0..gadget {
+                let mut acc = trapdoor[row * gadget + col] as u32;
+                for l in 0..n {
+                    acc = acc.wrapping_add(
+                        a_hat[row * n + l].wrapping_mul(trapdoor[(n + l) * gadget + col] as u32),
+                    );
+                }
+
+                let g: u32 = if col / LOG_MODULUS == row { 1 << (col % LOG_MODULUS) } else { 0 };
+                a_gadget[row * gadget + col] = g.wrapping_sub(acc);
+            }
+        }
+
+        // The perturbation `p = (p₁, p₂)` must have covariance `σ²I - σ_g²·[R; I]·[R; I]ᵀ`, so
+        // that the signature is spherical. `p₂` is sampled with covariance `(σ² - σ_g²)I`, and
+        // `p₁` conditioned on it has covariance `σ²I - c·RRᵀ`.
+        let mut gram = vec![0f64; width * width];
+        for i in 0..width {
+            for j in 0..=i {
+                let entry = dot(
+                    &trapdoor[i * gadget..(i + 1) * gadget],
+                    &trapdoor[j * gadget..(j + 1) * gadget],
+                ) as f64;
+                gram[i * width + j] = entry;
+                gram[j * width + i] = entry;
+            }
+        }
+
+        let sigma_gadget2 = SIGMA_GADGET * SIGMA_GADGET;
+        let sigma2 = 2.5f64.mul_add(
+            sigma_gadget2 * largest_eigenvalue(&gram, width),
+            SIGMA_ROUNDING * SIGMA_ROUNDING,
+        );
+        let c = sigma_gadget2 * sigma2 / (sigma2 - sigma_gadget2);
+
+        let covariance = gram
+            .iter()
+            .enumerate()
+            .map(|(idx, g)| {
+                let diagonal = if idx / width == idx % width {
+                    SIGMA_ROUNDING.mul_add(-SIGMA_ROUNDING, sigma2)
+                } else {
+                    0.0
+                };
+                c.mul_add(-g, diagonal)
+            })
+            .collect::<Vec<_>>();
+
+        let cholesky =
+            cholesky(&covariance, width).expect("perturbation covariance is positive definite");
+
+        Self {
+            key: LatticeVerifyingKey { n, a_hat, a_gadget },
+            trapdoor,
+            cholesky,
+            sigma: sigma2.sqrt(),
+        }
+    }
+
+    /// Returns the verifying key of this signing key.
+    pub const fn verifying_key(&self) -> &LatticeVerifyingKey {
+        &self.key
+    }
+
+    /// Signs the chunks of a generation, i.e. the augmented basis vectors `(eᵢ, xᵢ)` of the
+    /// subspace spanned by the source packets. Returns one signature per chunk.
+    ///
+    /// The generation id must be unique for every generation signed with this key, otherwise
+    /// packets of the two generations can be combined into valid signatures over data that's in
+    /// neither.
+    pub fn sign_generation<R: Rng>(
+        &self,
+        id: &[u8; 32],
+        chunks: &Chunks<Gf256>,
+        mut rng: R,
+    ) -> Result<Vec<LatticeSignature>, AuthError> {
+        let expected = (self.key.n / 8).saturating_sub(chunks.len());
+        let symbols = chunks.layout().symbols_per_chunk;
+        if symbols != expected {
+            return Err(AuthError::InvalidSymbolCount(symbols, expected));
+        }
+
+        let hash = self.key.hash_generation(id);
+        let mut unit = vec![Gf256::ZERO; chunks.len()];
+
+        Ok(chunks
+            .inner()
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                unit[i] = Gf256::ONE;
+                let twists = twists(&unit, chunk.symbols())
+                    .iter()
+                    .map(|bits| self.sign_bits(&hash, bits, &mut rng))
+                    .collect();
+                unit[i] = Gf256::ZERO;
+
+                LatticeSignature(twists)
+            })
+            .collect())
+    }
+
+    /// Samples a short `σ` with `A_τ · σ = q · bits (mod 2q)`.
+    fn sign_bits<R: Rng>(&self, hash: &[u32], bits: &[u32], rng: &mut R) -> Vec<i64> {
+        let n = self.key.n;
+
+        let extension = (0..n).map(|_| sample_z(rng, 0.0, self.sigma)).collect::<Vec<_>>();
+        let target = bits
+            .iter()
+            .enumerate()
+            .map(|(row, bit)| {
+                bit.wrapping_mul(Q).wrapping_sub(mul_row(&hash[row * n..(row + 1) * n], &extension))
+            })
+            .collect::<Vec<_>>();
+
+        let mut signature = self.sample_preimage(&target, rng);
+        signature.extend(extension);
+        signature
+    }
+
+    /// Samples a short `x` with `A · x = target (mod 2q)` from a spherical discrete Gaussian,
+    /// using the trapdoor.
+    fn sample_preimage<R: Rng>(&self, target: &[u32], rng: &mut R) -> Vec<i64> {
+        let n = self.key.n;
+        let (width, gadget) = (2 * n, LOG_MODULUS * n);
+        let sigma_gadget2 = SIGMA_GADGET * SIGMA_GADGET;
+        let sigma2 = self.sigma * self.sigma;
+
+        // Perturbation
+        let p2 = (0..gadget)
+            .map(|_| sample_z(rng, 0.0, (sigma2 - sigma_gadget2).sqrt()))
+            .collect::<Vec<_>>();
+        let scale = -sigma_gadget2 / (sigma2 - sigma_gadget2);
+        let normal = (0..width).map(|_| sample_normal(rng)).collect::<Vec<_>>();
+        let p1 = (0..width)
+            .map(|i| {
+                let mean = scale * dot(&self.trapdoor[i * gadget..(i + 1) * gadget], &p2) as f64;
+                let noise = (0..=i).map(|j| self.cholesky[i * width + j] * normal[j]).sum::<f64>();
+                sample_z(rng, mean + noise, SIGMA_ROUNDING)
+            })
+            .collect::<Vec<_>>();
+
+        let mut x = p1;
+        x.extend(p2);
+
+        // Gadget preimage of the remaining target.
+        let remaining = self.key.mul_trapdoor_part(&x);
+        let z = target
+            .iter()
+            .zip(remaining)
+            .flat_map(|(t, r)| sample_gadget(rng, t.wrapping_sub(r)))
+            .collect::<Vec<_>>();
+
+        // x += [R; I] · z
+        for (i, x) in x[..width].iter_mut().enumerate() {
+            *x += dot(&self.trapdoor[i * gadget..(i + 1) * gadget], &z);
+        }
+        for (x, z) in x[width..].iter_mut().zip(z) {
+            *x += z;
+        }
+
+        x
+    }
+}
+
+/// A public key for verifying lattice-based homomorphic signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatticeVerifyingKey {
+    /// The lattice dimension, i.e. the bit length of the packets.
+    n: usize,
+    /// The uniformly random part `Â`, with `n` rows and columns.
+    a_hat: Vec<u32>,
+    /// The trapdoor part `G - [I | Â]·R`, with `n` rows and `32n` columns.
+    a_gadget: Vec<u32>,
+}
+
+impl LatticeVerifyingKey {
+    /// Returns the lattice dimension, i.e. the bit length of the packets.
+    pub const fn dimension(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the size of the key in bytes.
+    pub const fn size(&self) -> usize {
+        (self.a_hat.len() + self.a_gadget.len()) * size_of::<u32>()
+    }
+
+    /// Hashes the generation id to the extension `H(τ)` of the matrix, with `n` rows and columns.
+    fn hash_generation(&self, id: &[u8; 32]) -> Vec<u32> {
+        (0..(self.n * self.n).div_ceil(8) as u64)
+            .flat_map(|block| {
+                let digest = Sha256::new()
+                    .chain_update(DST)
+                    .chain_update(id)
+                    .chain_update(block.to_le_bytes())
+                    .finalize();
+
+                digest
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect::<Vec<_>>()
+            })
+            .take(self.n * self.n)
+            .collect()
+    }
+
+    /// Computes `A · x (mod 2q)` for the first `34n` entries of `x`.
+    fn mul_trapdoor_part(&self, x: &[i64]) -> Vec<u32> {
+        let (n, gadget) = (self.n, LOG_MODULUS * self.n);
+
+        (0..n)
+            .map(|row| {
+                (x[row] as u32)
+                    .wrapping_add(mul_row(&self.a_hat[row * n..(row + 1) * n], &x[n..2 * n]))
+                    .wrapping_add(mul_row(
+                        &self.a_gadget[row * gadget..(row + 1) * gadget],
+                        &x[2 * n..2 * n + gadget],
+                    ))
+            })
+            .collect()
+    }
+
+    /// Verifies a single twist signature on the given bits.
+    fn verify_bits(&self, hash: &[u32], bits: &[u32], signature: &[i64]) -> bool {
+        let n = self.n;
+        if signature.len() != (LOG_MODULUS + 3) * n {
+            return false;
+        }
+
+        // Only the residues of the entries enter the check below, so the norm must be exact.
+        let norm = signature.iter().try_fold(0i128, |norm, x| {
+            if x.unsigned_abs() > MAX_ENTRY {
+                return None;
+            }
+
+            norm.checked_add(i128::from(*x).checked_mul(i128::from(*x))?)
+        });
+        if norm.is_none_or(|norm| norm > NORM_BOUND) {
+            return false;
+        }
+
+        let extension = &signature[(LOG_MODULUS + 2) * n..];
+        self.mul_trapdoor_part(signature).iter().zip(bits).enumerate().all(|(row, (a, bit))| {
+            a.wrapping_add(mul_row(&hash[row * n..(row + 1) * n], extension)) == bit.wrapping_mul(Q)
+        })
+    }
+}
+
+/// A lattice-based homomorphic signature of a packet.
+///
+/// GF(2^8) is a vector space over GF(2), but multiplying a packet by a coefficient isn't a
+/// combination of its bits. So instead of a single signature, the packet `v` carries signatures
+/// on its 8 twists `αᵇ · v`, where `α` generates GF(2^8). A coefficient `c` is a sum of powers
+/// `αᵇ`, so the twists of `c · v` are sums of twists of `v`, and relays can recode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatticeSignature(Vec<Vec<i64>>);
+
+impl LatticeSignature {
+    /// Returns the size of the signature in bytes, with each integer encoded as an `i64`.
+    pub fn size(&self) -> usize {
+        self.0.iter().map(Vec::len).sum::<usize>() * size_of::<i64>()
+    }
+
+    /// Combines signatures with the given coefficients into the signature of the combined packet.
+    fn combine<'a>(
+        signatures: impl IntoIterator<Item = (&'a Self, Gf256)>,
+        len: usize,
+    ) -> Result<Self, AuthError> {
+        let mut twists = vec![vec![0i64; len]; TWISTS];
+
+        for (signature, coefficient) in signatures {
+            if signature.0.len() != TWISTS || signature.0.iter().any(|t| t.len() != len) {
+                return Err(AuthError::InvalidSignature);
+            }
+
+            // αᵇ · c · v = Σ_b' bit_b'(αᵇ · c) · αᵇ' · v
+            let mut twisted = coefficient;
+            for acc in &mut twists {
+                for (b, twist) in signature.0.iter().enumerate() {
+                    if (twisted.0 >> b) & 1 == 1 {
+                        for (acc, x) in acc.iter_mut().zip(twist) {
+                            *acc = acc.checked_add(*x).ok_or(AuthError::InvalidSignature)?;
+                        }
+                    }
+                }
+
+                twisted *= Gf256(2);
+            }
+        }
+
+        Ok(Self(twists))
+    }
+}
+
+/// A coded packet together with its lattice-based homomorphic signature.
+#[derive(Debug, Clone)]
+pub struct LatticeSignedPacket {
+    /// The coded packet.
+    pub packet: RLNCPacket<Gf256>,
+    /// The signature of the augmented vector.
+    pub signature: LatticeSignature,
+}
+
+impl LatticeSignedPacket {
+    /// Recodes the packets into a new packet with a random linear combination, using the
+    /// provided random number generator. The signatures are combined with the same
+    /// coefficients, so relays can recode without the secret key.
+    ///
+    /// Every combination increases the norm of the signature, so packets can only be recoded a
+    /// few times before they stop verifying.
+    pub fn recode<R: Rng>(packets: &[Self], mut rng: R) -> Result<Self, AuthError> {
+        let Some(first) = packets.first() else {
+            return Err(RLNCError::NotEnoughPackets(0, 1).into());
+        };
+
+        let (chunk_count, symbol_count) =
+            (first.packet.coding_vector.len(), first.packet.data.len());
+        let len = first.signature.0.first().map_or(0, Vec::len);
+
+        let mut coding_vector = vec![Gf256::ZERO; chunk_count];
+        let mut data = vec![Gf256::ZERO; symbol_count];
+        let mut coefficients = Vec::with_capacity(packets.len());
+
+        for Self { packet, .. } in packets {
+            if packet.coding_vector.len() != chunk_count {
+                return Err(AuthError::InvalidCodingVectorLength(
+                    packet.coding_vector.len(),
+                    chunk_count,
+                ));
+            }
+
+            if packet.data.len() != symbol_count {
+                return Err(AuthError::InvalidSymbolCount(packet.data.len(), symbol_count));
+            }
+
+            let coefficient = Gf256(rng.random());
+
+            for (acc, c) in coding_vector.iter_mut().zip(&packet.coding_vector) {
+                *acc += coefficient * c;
+            }
+
+            for (acc, d) in data.iter_mut().zip(&packet.data) {
+                *acc += coefficient * d;
+            }
+
+            coefficients.push(coefficient);
+        }
+
+        let signature =
+            LatticeSignature::combine(packets.iter().map(|p| &p.signature).zip(coefficients), len)?;
+
+        Ok(Self { packet: RLNCPacket { coding_vector, data }, signature })
+    }
+}
+
+/// An encoder that signs the chunks of a generation with lattice-based homomorphic signatures,
+/// and produces [`LatticeSignedPacket`]s.
+#[derive(Debug)]
+pub struct LatticeEncoder {
+    encoder: Encoder<Gf256>,
+    signatures: Vec<LatticeSignature>,
+}
+
+impl LatticeEncoder {
+    /// Creates a new encoder from prepared chunks, signing them as generation `id` with `key`.
+    pub fn from_chunks<R: Rng>(
+        chunks: Chunks<Gf256>,
+        id: &[u8; 32],
+        key: &LatticeSigningKey,
+        rng: R,
+    ) -> Result<Self, AuthError> {
+        let signatures = key.sign_generation(id, &chunks, rng)?;
+        Ok(Self { encoder: Encoder::from_chunks(chunks), signatures })
+    }
+
+    /// Returns the layout of the chunks and the resulting coded packets.
+    pub const fn layout(&self) -> ChunkLayout {
+        self.encoder.layout()
+    }
+
+    /// Returns the underlying encoder.
+    pub const fn encoder(&self) -> &Encoder<Gf256> {
+        &self.encoder
+    }
+
+    /// Encodes the data with the given coding vector, and combines the chunk signatures with it.
+    pub fn encode_with_vector(
+        &self,
+        coding_vector: &[Gf256],
+    ) -> Result<LatticeSignedPacket, AuthError> {
+        let packet = self.encoder.encode_with_vector(coding_vector)?;
+
+        let len = self.signatures.first().map_or(0, |s| s.0.first().map_or(0, Vec::len));
+        let signature = LatticeSignature::combine(
+            self.signatures.iter().zip(coding_vector.iter().copied()),
+            len,
+        )?;
+
+        Ok(LatticeSignedPacket { packet, signature })
+    }
+
+    /// Encodes the data with a random coding vector, using the provided random number generator.
+    pub fn encode<R: Rng>(&self, mut rng: R) -> Result<LatticeSignedPacket, AuthError> {
+        let coding_vector =
+            (0..self.signatures.len()).map(|_| Gf256(rng.random())).collect::<Vec<_>>();
+
+        self.encode_with_vector(&coding_vector)
+    }
+}
+
+/// Verifies [`LatticeSignedPacket`]s of a single generation.
+#[derive(Debug)]
+pub struct LatticeVerifier {
+    key: LatticeVerifyingKey,
+    hash: Vec<u32>,
+    chunk_count: usize,
+}
+
+impl LatticeVerifier {
+    /// Creates a new verifier for generation `id` with the given layout.
+    pub fn new(key: LatticeVerifyingKey, id: &[u8; 32], layout: ChunkLayout) -> Self {
+        let hash = key.hash_generation(id);
+        Self { key, hash, chunk_count: layout.chunk_count }
+    }
+
+    /// Verifies a single signed packet. All twists are verified, so that a relay never recodes
+    /// with an invalid one.
+    pub fn verify(&self, packet: &LatticeSignedPacket) -> Result<(), AuthError> {
+        let LatticeSignedPacket { packet, signature } = packet;
+
+        if packet.coding_vector.len() != self.chunk_count {
+            return Err(AuthError::InvalidCodingVectorLength(
+                packet.coding_vector.len(),
+                self.chunk_count,
+            ));
+        }
+
+        let symbols = (self.key.n / 8).saturating_sub(self.chunk_count);
+        if packet.data.len() != symbols {
+            return Err(AuthError::InvalidSymbolCount(packet.data.len(), symbols));
+        }
+
+        if signature.0.len() != TWISTS {
+            return Err(AuthError::InvalidSignature);
+        }
+
+        let valid = twists(&packet.coding_vector, &packet.data)
+            .iter()
+            .zip(&signature.0)
+            .all(|(bits, twist)| self.key.verify_bits(&self.hash, bits, twist));
+
+        if !valid {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the lattice dimension for packets with the given layout, i.e. their bit length.
+const fn dimension(layout: ChunkLayout) -> usize {
+    8 * (layout.chunk_count + layout.symbols_per_chunk)
+}
+
+/// Returns the bits of the twists `αᵇ · (coding_vector, data)` of an augmented vector.
+fn twists(coding_vector: &[Gf256], data: &[Gf256]) -> Vec<Vec<u32>> {
+    let mut twist = Gf256::ONE;
+
+    (0..TWISTS)
+        .map(|_| {
+            let bits = coding_vector
+                .iter()
+                .chain(data)
+                .flat_map(|e| (0..8).map(move |b| u32::from(((twist * e).0 >> b) & 1)))
+                .collect();
+            twist *= Gf256(2);
+            bits
+        })
+        .collect()
+}
+
+/// Computes the inner product of a row with an integer vector modulo `2q`.
+fn mul_row(row: &[u32], x: &[i64]) -> u32 {
+    row.iter().zip(x).fold(0u32, |acc, (a, x)| acc.wrapping_add(a.wrapping_mul(*x as u32)))
+}
+
+/// Computes the inner product of two integer vectors.
+fn dot(a: &[i64], b: &[i64]) -> i64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Samples a `z` with `g · z = u (mod 2q)` for the gadget vector `g = (1, 2, ..., 2^31)`.
+fn sample_gadget<R: Rng>(rng: &mut R, mut u: u32) -> [i64; LOG_MODULUS] {
+    let mut z = [0i64; LOG_MODULUS];
+
+    for z in &mut z {
+        // Sample from the coset `2Z + (u mod 2)`.
+        let bit = i64::from(u & 1);
+        *z = 2 * sample_z(rng, -(bit as f64) / 2.0, SIGMA_GADGET / 2.0) + bit;
+        u = u.wrapping_sub(*z as u32) >> 1;
+    }
+
+    z
+}
+
+/// Samples an integer from the discrete Gaussian with the given center and standard deviation,
+/// by rejection sampling.
+fn sample_z<R: Rng>(rng: &mut R, center: f64, sigma: f64) -> i64 {
+    let min = TAIL.mul_add(-sigma, center).floor() as i64;
+    let max = TAIL.mul_add(sigma, center).ceil() as i64;
+
+    loop {
+        let x = rng.random_range(min..=max);
+        let d = x as f64 - center;
+        if rng.random::<f64>() < (-d * d / (2.0 * sigma * sigma)).exp() {
+            return x;
+        }
+    }
+}
+
+/// Samples from the standard normal distribution (Box–Muller).
+fn sample_normal<R: Rng>(rng: &mut R) -> f64 {
+    let (u1, u2) = (1.0 - rng.random::<f64>(), rng.random::<f64>());
+    (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// Estimates the largest eigenvalue of a symmetric positive semi-definite matrix with power
+/// iteration.
+fn largest_eigenvalue(matrix: &[f64], n: usize) -> f64 {
+    let mut v = vec![1.0; n];
+    let mut eigenvalue = 0.0;
+
+    for _ in 0..100 {
+        let w = (0..n)
+            .map(|i| matrix[i * n..(i + 1) * n].iter().zip(&v).map(|(a, v)| a * v).sum::<f64>())
+            .collect::<Vec<_>>();
+        eigenvalue = w.iter().map(|x| x * x).sum::<f64>().sqrt();
+        v = w.into_iter().map(|x| x / eigenvalue).collect();
+    }
+
+    eigenvalue
+}
+
+/// Computes the lower triangular Cholesky factor of a symmetric matrix, or `None` if it's not
+/// positive definite.
+fn cholesky(matrix: &[f64], n: usize) -> Option<Vec<f64>> {
+    let mut l = vec![0.0; n * n];
+
+    for i in 0..n {
+        for j in 0..=i {
+            let dot = (0..j).map(|k| l[i * n + k] * l[j * n + k]).sum::<f64>();
+            if i == j {
+                let d = matrix[i * n + i] - dot;
+                if d <= 0.0 {
+                    return None;
+                }
+                l[i * n + j] = d.sqrt();
+            } else {
+                l[i * n + j] = (matrix[i * n + j] - dot) / l[j * n + j];
+            }
+        }
+    }
+
+    Some(l)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rand::Rng;
+    use zc_rlnc::decode::Decoder;
+
+    use super::*;
+
+    #[test]
+    fn test_lattice_encode_recode_decode() {
+        let data = rand::rng().random_iter().take(6).collect::<Vec<_>>();
+        let chunk_count = 2;
+        let id = [7u8; 32];
+
+        let chunks = Encoder::<Gf256>::prepare(&data, chunk_count).unwrap();
+        let layout = chunks.layout();
+
+        let start = Instant::now();
+        let key = LatticeSigningKey::generate(layout, rand::rng());
+        println!(
+            "Key generation time (n = {}): {:?}, key size: {} bytes",
+            key.verifying_key().dimension(),
+            start.elapsed(),
+            key.verifying_key().size()
+        );
+
+        let start = Instant::now();
+        let encoder = LatticeEncoder::from_chunks(chunks, &id, &key, rand::rng()).unwrap();
+        println!("Signing time ({chunk_count} chunks): {:?}", start.elapsed());
+
+        let verifier = LatticeVerifier::new(key.verifying_key().clone(), &id, layout);
+
+        let packets = (0..3).map(|_| encoder.encode(rand::rng()).unwrap()).collect::<Vec<_>>();
+        let start = Instant::now();
+        for packet in &packets {
+            verifier.verify(packet).unwrap();
+        }
+        println!(
+            "Verification time: {:?}, signature size: {} bytes",
+            start.elapsed() / packets.len() as u32,
+            packets[0].signature.size()
+        );
+
+        // Tampered packets and packets of other generations are rejected.
+        let mut polluted = packets[0].clone();
+        polluted.packet.data[0] += Gf256(1);
+        assert!(matches!(verifier.verify(&polluted), Err(AuthError::InvalidPacket)));
+
+        let other = LatticeVerifier::new(key.verifying_key().clone(), &[8u8; 32], layout);
+        assert!(other.verify(&packets[0]).is_err());
+
+        // Packets are recoded once by relays, and the recoded packets are recoded again.
+        let mut decoder = Decoder::<Gf256>::from_layout(layout).unwrap();
+        let decoded = loop {
+            let relayed = (0..2)
+                .map(|_| LatticeSignedPacket::recode(&packets, rand::rng()).unwrap())
+                .collect::<Vec<_>>();
+            let recoded = LatticeSignedPacket::recode(&relayed, rand::rng()).unwrap();
+            verifier.verify(&recoded).unwrap();
+
+            if let Some(decoded) = decoder.decode(recoded.packet).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+
+        // Entries that are equal modulo 2^32 but huge don't wrap the norm around.
+        let mut oversized = packets[0].clone();
+        for x in &mut oversized.signature.0[0][..8] {
+            *x += 1 << 62;
+        }
+        assert!(matches!(verifier.verify(&oversized), Err(AuthError::InvalidPacket)));
+
+        // Combining huge entries fails instead of overflowing.
+        let mut huge = packets[0].signature.clone();
+        huge.0[0][0] = i64::MAX;
+        let len = huge.0[0].len();
+        assert!(matches!(
+            LatticeSignature::combine([(&huge, Gf256(1)), (&huge, Gf256(1))], len),
+            Err(AuthError::InvalidSignature)
+        ));
+    }
+}
@@ -1,3 +1,14 @@
-//! Authentication & integrity protection primitives and mechanisms for ZeroCast.
+//! Authentication & integrity protection primitives and mechanisms for `ZeroCast`.
 
+pub mod authenticated;
+pub mod batch;
 pub mod commit;
+pub mod hash;
+pub mod hiding;
+pub mod homomorphic;
+pub mod lattice;
+pub mod mac;
+pub mod merkle;
+pub mod sign;
+mod table;
+pub mod verify;
@@ -0,0 +1,302 @@
+//! This module implements homomorphic MACs for network coding (Agrawal–Boneh style), for
+//! receivers that share a key with the source.
+use rand::Rng;
+use subtle::ConstantTimeEq;
+use zc_rlnc::{
+    RLNCError,
+    encode::{Encoder, random_coefficient},
+    primitives::{
+        ChunkLayout, Chunks,
+        field::{Field, expand_field_elements},
+        packet::RLNCPacket,
+    },
+};
+
+use crate::verify::AuthError;
+
+/// The domain separation tag for deriving the MAC key vectors.
+const DST: &[u8] = b"RLNC_HOMOMORPHIC_MAC";
+
+/// A homomorphic MAC over the augmented vectors `(c, d)` of the coded packets of a generation.
+///
+/// # Idea
+/// For every tag `j`, a secret key vector `uⱼ` is derived from the shared key and the generation
+/// id with a PRF (SHA-256). The tag of an augmented vector `v` is the inner product
+///
+/// ```text
+/// tⱼ(v) = ⟨v, uⱼ⟩
+/// ```
+///
+/// which is linear in `v`, so the tags of a linear combination of packets are the same linear
+/// combination of their tags, and recoding preserves them. Verification is a single inner
+/// product per tag, without any elliptic curve operations.
+///
+/// # Security
+/// Anyone holding the key can forge tags, so this is only suitable when all receivers are
+/// trusted not to pollute (e.g. inside a single operator's cluster). An outsider forges a
+/// packet with probability `|F|^-tags`, so small fields need multiple tags: with
+/// [`Gf256`](zc_rlnc::primitives::field::Gf256), 8 tags give 64 bits of security.
+/// Because the key vectors are derived per generation id, tags of different generations can't be
+/// combined.
+#[derive(Debug)]
+pub struct HomomorphicMac<F: Field> {
+    vectors: Vec<Vec<F>>,
+    chunk_count: usize,
+}
+
+impl<F: Field> HomomorphicMac<F> {
+    /// Creates the MAC for generation `id` with the given layout, deriving `tags` key vectors
+    /// from the shared key.
+    pub fn new(key: &[u8; 32], id: &[u8; 32], layout: ChunkLayout, tags: usize) -> Self {
+        let len = layout.chunk_count + layout.symbols_per_chunk;
+        let vectors = (0..tags).map(|j| prf(key, id, j, len)).collect();
+
+        Self { vectors, chunk_count: layout.chunk_count }
+    }
+
+    /// Returns the number of tags per packet.
+    pub const fn tags(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Computes the tags of the augmented vector `(coding_vector, data)`.
+    pub fn tag(&self, coding_vector: &[F], data: &[F]) -> Result<Vec<F>, AuthError> {
+        if coding_vector.len() != self.chunk_count {
+            return Err(AuthError::InvalidCodingVectorLength(coding_vector.len(), self.chunk_count));
+        }
+
+        let symbols = self.vectors.first().map_or(0, |u| u.len() - self.chunk_count);
+        if data.len() != symbols {
+            return Err(AuthError::InvalidSymbolCount(data.len(), symbols));
+        }
+
+        Ok(self
+            .vectors
+            .iter()
+            .map(|u| {
+                let (uc, ud) = u.split_at(self.chunk_count);
+                inner_product(coding_vector, uc) + inner_product(data, ud)
+            })
+            .collect())
+    }
+
+    /// Computes the tags of every source chunk, i.e. of the augmented basis vectors `(eᵢ, xᵢ)`.
+    pub fn tag_chunks(&self, chunks: &Chunks<F>) -> Result<Vec<Vec<F>>, AuthError> {
+        let mut unit = vec![F::ZERO; chunks.len()];
+
+        chunks
+            .inner()
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                unit[i] = F::ONE;
+                let tags = self.tag(&unit, chunk.symbols());
+                unit[i] = F::ZERO;
+                tags
+            })
+            .collect()
+    }
+
+    /// Verifies the tags of a packet.
+    pub fn verify(&self, packet: &TaggedPacket<F>) -> Result<(), AuthError> {
+        if packet.tags.len() != self.tags() {
+            return Err(AuthError::InvalidTagCount(packet.tags.len(), self.tags()));
+        }
+
+        // The tags are compared in constant time, so that timing doesn't reveal how many match.
+        let tags = self.tag(&packet.packet.coding_vector, &packet.packet.data)?;
+        if !bool::from(tags.as_slice().ct_eq(&packet.tags)) {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+}
+
+/// A coded packet together with its homomorphic MAC tags.
+#[derive(Debug, Clone)]
+pub struct TaggedPacket<F: Field> {
+    /// The coded packet.
+    pub packet: RLNCPacket<F>,
+    /// The MAC tags of the augmented vector.
+    pub tags: Vec<F>,
+}
+
+impl<F: Field> TaggedPacket<F> {
+    /// Recodes the packets into a new packet with a random linear combination, using the
+    /// provided random number generator. The tags are combined with the same coefficients, so
+    /// relays can recode without the key.
+    pub fn recode<R: Rng>(packets: &[Self], mut rng: R) -> Result<Self, AuthError> {
+        let Some(first) = packets.first() else {
+            return Err(RLNCError::NotEnoughPackets(0, 1).into());
+        };
+
+        let (chunk_count, symbol_count, tag_count) =
+            (first.packet.coding_vector.len(), first.packet.data.len(), first.tags.len());
+
+        let mut coding_vector = vec![F::ZERO; chunk_count];
+        let mut data = vec![F::ZERO; symbol_count];
+        let mut tags = vec![F::ZERO; tag_count];
+
+        for packet in packets {
+            if packet.packet.coding_vector.len() != chunk_count {
+                return Err(AuthError::InvalidCodingVectorLength(
+                    packet.packet.coding_vector.len(),
+                    chunk_count,
+                ));
+            }
+
+            if packet.packet.data.len() != symbol_count {
+                return Err(AuthError::InvalidSymbolCount(packet.packet.data.len(), symbol_count));
+            }
+
+            if packet.tags.len() != tag_count {
+                return Err(AuthError::InvalidTagCount(packet.tags.len(), tag_count));
+            }
+
+            let coefficient = random_coefficient::<F, _>(&mut rng);
+
+            for (acc, c) in coding_vector.iter_mut().zip(&packet.packet.coding_vector) {
+                *acc += coefficient * c;
+            }
+
+            for (acc, d) in data.iter_mut().zip(&packet.packet.data) {
+                *acc += coefficient * d;
+            }
+
+            for (acc, t) in tags.iter_mut().zip(&packet.tags) {
+                *acc += coefficient * t;
+            }
+        }
+
+        Ok(Self { packet: RLNCPacket { coding_vector, data }, tags })
+    }
+}
+
+/// An encoder that tags the chunks of a generation with a [`HomomorphicMac`], and produces
+/// [`TaggedPacket`]s.
+#[derive(Debug)]
+pub struct MacEncoder<F: Field> {
+    encoder: Encoder<F>,
+    tags: Vec<Vec<F>>,
+}
+
+impl<F: Field> MacEncoder<F> {
+    /// Creates a new encoder from prepared chunks, tagging them with `mac`.
+    pub fn from_chunks(chunks: Chunks<F>, mac: &HomomorphicMac<F>) -> Result<Self, AuthError> {
+        let tags = mac.tag_chunks(&chunks)?;
+        Ok(Self { encoder: Encoder::from_chunks(chunks), tags })
+    }
+
+    /// Returns the underlying encoder.
+    pub const fn encoder(&self) -> &Encoder<F> {
+        &self.encoder
+    }
+
+    /// Encodes the data with the given coding vector, and combines the chunk tags with it.
+    pub fn encode_with_vector(&self, coding_vector: &[F]) -> Result<TaggedPacket<F>, AuthError> {
+        let packet = self.encoder.encode_with_vector(coding_vector)?;
+
+        let mut tags = vec![F::ZERO; self.tags.first().map_or(0, Vec::len)];
+        for (chunk_tags, c) in self.tags.iter().zip(coding_vector) {
+            for (acc, t) in tags.iter_mut().zip(chunk_tags) {
+                *acc += *t * c;
+            }
+        }
+
+        Ok(TaggedPacket { packet, tags })
+    }
+
+    /// Encodes the data with a random coding vector, using the provided random number generator.
+    pub fn encode<R: Rng>(&self, mut rng: R) -> Result<TaggedPacket<F>, AuthError> {
+        let coding_vector =
+            (0..self.tags.len()).map(|_| random_coefficient(&mut rng)).collect::<Vec<_>>();
+
+        self.encode_with_vector(&coding_vector)
+    }
+}
+
+/// Computes the inner product of two vectors.
+fn inner_product<F: Field>(a: &[F], b: &[F]) -> F {
+    a.iter().zip(b).fold(F::ZERO, |acc, (a, b)| acc + *a * b)
+}
+
+/// Derives a key vector of `len` field elements for tag `index` from the key and generation id,
+/// see [`expand_field_elements`].
+fn prf<F: Field>(key: &[u8; 32], id: &[u8; 32], index: usize, len: usize) -> Vec<F> {
+    expand_field_elements(DST, &[key, id, &(index as u64).to_le_bytes()], len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use blstrs::Scalar;
+    use rand::Rng;
+    use zc_rlnc::{decode::Decoder, primitives::field::Gf256};
+
+    use super::*;
+    use crate::{
+        commit::{ChunkCommitments, PedersenCommitter},
+        verify::Verifier,
+    };
+
+    /// Tags, recodes, verifies and decodes packets of a generation over `F`.
+    fn mac_roundtrip<F: Field>(chunk_count: usize, tags: usize) {
+        let data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let (key, id) = ([1u8; 32], [2u8; 32]);
+
+        let chunks = Chunks::<F>::new(&data, chunk_count).unwrap();
+        let layout = chunks.layout();
+        let mac = HomomorphicMac::<F>::new(&key, &id, layout, tags);
+        let encoder = MacEncoder::from_chunks(chunks, &mac).unwrap();
+
+        let packet = encoder.encode(rand::rng()).unwrap();
+        let start = Instant::now();
+        mac.verify(&packet).unwrap();
+        println!("Verification time ({} symbols): {:?}", layout.symbols_per_chunk, start.elapsed());
+
+        // Tampered packets and packets of other generations are rejected.
+        let mut polluted = packet.clone();
+        polluted.packet.data[0] += F::ONE;
+        assert!(matches!(mac.verify(&polluted), Err(AuthError::InvalidPacket)));
+
+        let other = HomomorphicMac::<F>::new(&key, &[3u8; 32], layout, tags);
+        assert!(other.verify(&packet).is_err());
+
+        let mut decoder = Decoder::<F>::from_layout(layout).unwrap();
+        let decoded = loop {
+            let relayed = (0..3).map(|_| encoder.encode(rand::rng()).unwrap()).collect::<Vec<_>>();
+            let recoded = TaggedPacket::recode(&relayed, rand::rng()).unwrap();
+            mac.verify(&recoded).unwrap();
+
+            if let Some(decoded) = decoder.decode(recoded.packet).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_mac_gf256() {
+        mac_roundtrip::<Gf256>(16, 8);
+    }
+
+    #[test]
+    fn test_mac_scalar() {
+        mac_roundtrip::<Scalar>(4, 1);
+
+        // Compare against verifying the same packet with Pedersen commitments.
+        let data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let chunks = Encoder::<Scalar>::prepare(&data, 4).unwrap();
+        let committer = PedersenCommitter::new([0u8; 32], chunks.layout().symbols_per_chunk);
+        let commitments = ChunkCommitments::new(&committer, &chunks).unwrap();
+        let verifier = Verifier::new(committer, commitments);
+        let packet = Encoder::from_chunks(chunks).encode(rand::rng()).unwrap();
+
+        let start = Instant::now();
+        verifier.verify(&packet).unwrap();
+        println!("Pedersen verification time: {:?}", start.elapsed());
+    }
+}
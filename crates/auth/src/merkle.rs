@@ -0,0 +1,344 @@
+//! Merkle tree authentication of coded packets, for source-only coding where relays never
+//! recode.
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use zc_rlnc::{
+    RLNCError,
+    encode::Encoder,
+    primitives::{field::Field, packet::RLNCPacket},
+};
+
+use crate::verify::AuthError;
+
+/// The domain separation prefix of leaf hashes.
+const LEAF_PREFIX: u8 = 0x00;
+
+/// The domain separation prefix of inner node hashes.
+const NODE_PREFIX: u8 = 0x01;
+
+/// A SHA-256 hash.
+pub type Hash = [u8; 32];
+
+/// A binary Merkle tree over the hashes of coded packets.
+///
+/// Leaves are `SHA-256(0x00 || packet)` over the serialized packet, and inner nodes are
+/// `SHA-256(0x01 || left || right)`. The leaves are padded with zero hashes to the next power
+/// of two, so all proofs of a tree have the same length.
+///
+/// # Security
+/// The tree only authenticates the exact packets it was built over, so it's binding even for
+/// small fields like [`Gf256`](zc_rlnc::primitives::field::Gf256), and much cheaper than
+/// elliptic curve based schemes. But recoded packets aren't in the tree and can't be verified,
+/// so it only works in source-only mode. The root must be transmitted to receivers through an
+/// authenticated channel, e.g. signed with [`SecretKey::sign`](crate::sign::SecretKey::sign).
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    /// The layers of the tree, from the (padded) leaves up to the root.
+    layers: Vec<Vec<Hash>>,
+    /// The number of leaves before padding.
+    len: usize,
+}
+
+impl MerkleTree {
+    /// Builds a tree over the given leaf hashes.
+    ///
+    /// # Panics
+    /// Panics if there are no leaves.
+    pub fn new(mut leaves: Vec<Hash>) -> Self {
+        assert!(!leaves.is_empty(), "tree must have at least one leaf");
+
+        let len = leaves.len();
+        leaves.resize(len.next_power_of_two(), [0u8; 32]);
+
+        let mut layers = vec![leaves];
+        while let Some(layer) = layers.last() &&
+            layer.len() > 1
+        {
+            let next = layer.chunks_exact(2).map(|pair| hash_node(&pair[0], &pair[1])).collect();
+            layers.push(next);
+        }
+
+        Self { layers, len }
+    }
+
+    /// Builds a tree over the hashes of the given packets.
+    pub fn from_packets<F: Field>(packets: &[RLNCPacket<F>]) -> Self {
+        #[cfg(feature = "parallel")]
+        let leaves = {
+            use rayon::prelude::*;
+            packets.par_iter().map(hash_packet).collect()
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let leaves = packets.iter().map(hash_packet).collect();
+
+        Self::new(leaves)
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> Hash {
+        self.layers.last().expect("tree has at least one layer")[0]
+    }
+
+    /// Returns the number of leaves (before padding).
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tree has no leaves, which is never the case.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the inclusion proof of the leaf at `index`, or `None` if it's out of bounds.
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len {
+            return None;
+        }
+
+        let siblings = self.layers[..self.layers.len() - 1]
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| layer[(index >> level) ^ 1])
+            .collect();
+
+        Some(MerkleProof { index, siblings })
+    }
+}
+
+/// An inclusion proof of a leaf in a [`MerkleTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// The index of the leaf.
+    pub index: usize,
+    /// The sibling hashes on the path from the leaf to the root.
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+    /// Verifies that `leaf` is at the proof's index in the tree with the given root.
+    pub fn verify(&self, root: &Hash, leaf: &Hash) -> Result<(), AuthError> {
+        // No tree is that deep, and longer proofs would overflow the shifts below.
+        if self.siblings.len() >= usize::BITS as usize || self.index >> self.siblings.len() != 0 {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        let computed = self.siblings.iter().enumerate().fold(*leaf, |node, (level, sibling)| {
+            if (self.index >> level) & 1 == 0 {
+                hash_node(&node, sibling)
+            } else {
+                hash_node(sibling, &node)
+            }
+        });
+
+        if computed != *root {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+}
+
+/// A coded packet together with its inclusion proof.
+#[derive(Debug, Clone)]
+pub struct ProvenPacket<F: Field> {
+    /// The coded packet.
+    pub packet: RLNCPacket<F>,
+    /// The inclusion proof of the packet in the generation's tree.
+    pub proof: MerkleProof,
+}
+
+impl<F: Field> ProvenPacket<F> {
+    /// Verifies the packet against the root of the generation's tree.
+    pub fn verify(&self, root: &Hash) -> Result<(), AuthError> {
+        self.proof.verify(root, &hash_packet(&self.packet))
+    }
+}
+
+/// A source that encodes a fixed set of packets up front and commits to them with a
+/// [`MerkleTree`]. Only these packets can be sent, so the number of packets must cover the
+/// expected losses.
+#[derive(Debug)]
+pub struct MerkleEncoder<F: Field> {
+    packets: Vec<RLNCPacket<F>>,
+    tree: MerkleTree,
+}
+
+impl<F: Field> MerkleEncoder<F> {
+    /// Encodes `count` packets with random coding vectors, and commits to them. Fails if `count`
+    /// is 0.
+    pub fn new<R: Rng>(encoder: &Encoder<F>, count: usize, rng: R) -> Result<Self, AuthError> {
+        Self::try_from_packets(encoder.encode_batch(rng, count)?)
+    }
+
+    /// Encodes the systematic packets, i.e. the source chunks with unit coding vectors, followed
+    /// by `repair` packets with random coding vectors, and commits to them.
+    pub fn systematic<R: Rng>(
+        encoder: &Encoder<F>,
+        repair: usize,
+        mut rng: R,
+    ) -> Result<Self, AuthError> {
+        let chunk_count = encoder.chunk_count();
+        let unit_vectors = (0..chunk_count)
+            .map(|i| {
+                let mut vector = vec![F::ZERO; chunk_count];
+                vector[i] = F::ONE;
+                vector
+            })
+            .collect::<Vec<_>>();
+
+        let mut packets = encoder.encode_batch_with_vectors(&unit_vectors)?;
+        packets.extend(encoder.encode_batch(&mut rng, repair)?);
+
+        Self::try_from_packets(packets)
+    }
+
+    /// Commits to the given packets.
+    ///
+    /// # Panics
+    /// Panics if there are no packets.
+    pub fn from_packets(packets: Vec<RLNCPacket<F>>) -> Self {
+        let tree = MerkleTree::from_packets(&packets);
+        Self { packets, tree }
+    }
+
+    /// Commits to the given packets, or fails if there are none.
+    fn try_from_packets(packets: Vec<RLNCPacket<F>>) -> Result<Self, AuthError> {
+        if packets.is_empty() {
+            return Err(RLNCError::ZeroPacketCount.into());
+        }
+
+        Ok(Self::from_packets(packets))
+    }
+
+    /// Returns the root of the tree, which must be sent to receivers through an authenticated
+    /// channel.
+    pub fn root(&self) -> Hash {
+        self.tree.root()
+    }
+
+    /// Returns the number of packets.
+    pub const fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Returns true if there are no packets.
+    pub const fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Returns the packet at `index` with its inclusion proof.
+    pub fn packet(&self, index: usize) -> Option<ProvenPacket<F>> {
+        Some(ProvenPacket {
+            packet: self.packets.get(index)?.clone(),
+            proof: self.tree.proof(index)?,
+        })
+    }
+
+    /// Returns an iterator over all packets with their inclusion proofs.
+    pub fn packets(&self) -> impl Iterator<Item = ProvenPacket<F>> + '_ {
+        (0..self.packets.len()).filter_map(|i| self.packet(i))
+    }
+}
+
+/// Hashes a serialized packet into a leaf.
+fn hash_packet<F: Field>(packet: &RLNCPacket<F>) -> Hash {
+    Sha256::new().chain_update([LEAF_PREFIX]).chain_update(packet.to_bytes()).finalize().into()
+}
+
+/// Hashes two child nodes into their parent.
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use blstrs::Scalar;
+    use rand::Rng;
+    use zc_rlnc::{decode::Decoder, primitives::field::Gf256};
+
+    use super::*;
+
+    #[test]
+    fn test_merkle_encode_verify_decode() {
+        let data = rand::rng().random_iter().take(1024 * 64).collect::<Vec<_>>();
+        let chunk_count = 32;
+        let packet_count = 48;
+
+        let encoder = Encoder::<Gf256>::new(&data, chunk_count).unwrap();
+        let start = Instant::now();
+        let source = MerkleEncoder::new(&encoder, packet_count, rand::rng()).unwrap();
+        println!("Tree construction time ({packet_count} packets): {:?}", start.elapsed());
+
+        let root = source.root();
+        let mut packets = source.packets().collect::<Vec<_>>();
+        assert_eq!(packets.len(), packet_count);
+
+        let start = Instant::now();
+        for packet in &packets {
+            packet.verify(&root).unwrap();
+        }
+        println!("Verification time ({packet_count} packets): {:?}", start.elapsed());
+
+        // Tampered packets, or proofs for another index, are rejected.
+        let mut polluted = packets[0].clone();
+        polluted.packet.data[0] += Gf256(1);
+        assert!(matches!(polluted.verify(&root), Err(AuthError::InvalidPacket)));
+
+        let mut moved = packets[1].clone();
+        moved.proof.index = 0;
+        assert!(moved.verify(&root).is_err());
+        moved.proof.index = 1 << moved.proof.siblings.len();
+        assert!(moved.verify(&root).is_err());
+
+        // Proofs that are too long for any tree are rejected without overflowing.
+        let mut oversized = packets[1].clone();
+        oversized.proof.siblings = vec![[0u8; 32]; 64];
+        assert!(matches!(oversized.verify(&root), Err(AuthError::InvalidPacket)));
+
+        assert!(matches!(
+            MerkleEncoder::new(&encoder, 0, rand::rng()),
+            Err(AuthError::Coding(RLNCError::ZeroPacketCount))
+        ));
+
+        // Drop a quarter of the packets.
+        packets.retain(|p| p.proof.index % 4 != 0);
+
+        let mut decoder = Decoder::<Gf256>::from_layout(encoder.layout()).unwrap();
+        let mut decoded = None;
+        for packet in packets {
+            packet.verify(&root).unwrap();
+            decoded = decoder.decode(packet.packet).unwrap().or(decoded);
+        }
+
+        assert_eq!(decoded.unwrap(), data);
+    }
+
+    #[test]
+    fn test_merkle_systematic() {
+        let data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let chunk_count = 5;
+
+        let encoder = Encoder::<Scalar>::new(&data, chunk_count).unwrap();
+        let source = MerkleEncoder::systematic(&encoder, 2, rand::rng()).unwrap();
+        assert_eq!(source.len(), chunk_count + 2);
+
+        let root = source.root();
+        let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+        let mut decoded = None;
+        for packet in source.packets().skip(2) {
+            packet.verify(&root).unwrap();
+            decoded = decoder.decode(packet.packet).unwrap().or(decoded);
+        }
+
+        assert_eq!(decoded.unwrap(), data);
+        assert!(source.packet(chunk_count + 2).is_none());
+    }
+}
@@ -0,0 +1,300 @@
+//! BLS signatures over BLS12-381, used to sign generation headers.
+use blstrs::{Bls12, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar};
+use group::{Curve, Group, prime::PrimeCurveAffine};
+use pairing::{MillerLoopResult, MultiMillerLoop};
+use rand::Rng;
+
+use crate::{authenticated::GenerationHeader, verify::AuthError};
+
+/// The domain separation tag used to hash messages to G2. This is the ciphersuite of the basic
+/// (minimal public key size) scheme from the IETF BLS signature draft, so signatures are
+/// compatible with other implementations of that scheme.
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// A BLS secret key.
+#[derive(Clone)]
+pub struct SecretKey(Scalar);
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SecretKey").finish_non_exhaustive()
+    }
+}
+
+impl SecretKey {
+    /// Deterministically derives a secret key from at least 32 bytes of input key material,
+    /// following the IETF `KeyGen` procedure.
+    pub fn from_ikm(ikm: &[u8]) -> Result<Self, AuthError> {
+        let sk =
+            blst::min_pk::SecretKey::key_gen(ikm, &[]).map_err(|_| AuthError::InvalidEncoding)?;
+        let scalar = Scalar::from_bytes_be(&sk.to_bytes());
+
+        Option::from(scalar).map(Self).ok_or(AuthError::InvalidEncoding)
+    }
+
+    /// Generates a new random secret key.
+    pub fn random<R: Rng>(mut rng: R) -> Self {
+        let mut ikm = [0u8; 32];
+        rng.fill(&mut ikm);
+
+        Self::from_ikm(&ikm).expect("32 bytes of key material")
+    }
+
+    /// Returns the secret scalar.
+    pub(crate) const fn scalar(&self) -> &Scalar {
+        &self.0
+    }
+
+    /// Returns the public key of this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(G1Projective::generator() * self.0)
+    }
+
+    /// Signs the message.
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        Signature(hash_to_g2(msg) * self.0)
+    }
+}
+
+/// A BLS public key in G1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(G1Projective);
+
+impl PublicKey {
+    /// Decodes a compressed public key, rejecting the identity.
+    pub fn from_bytes(bytes: &[u8; 48]) -> Result<Self, AuthError> {
+        Option::<G1Affine>::from(G1Affine::from_compressed(bytes))
+            .filter(|p| !bool::from(p.is_identity()))
+            .map(|p| Self(p.into()))
+            .ok_or(AuthError::InvalidEncoding)
+    }
+
+    /// Returns the compressed public key.
+    pub fn to_bytes(&self) -> [u8; 48] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Aggregates public keys, e.g. to verify a single message signed by all of them.
+    ///
+    /// # Security
+    /// Aggregating public keys is only safe if every key comes with a proof of possession of its
+    /// secret key, as otherwise an attacker can pick a rogue key that cancels out the others.
+    pub fn aggregate<'a>(keys: impl IntoIterator<Item = &'a Self>) -> Self {
+        Self(keys.into_iter().map(|k| k.0).sum())
+    }
+
+    /// Verifies the signature over `msg`.
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), AuthError> {
+        signature.verify_aggregate([(self, msg)])
+    }
+}
+
+/// A BLS signature in G2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature(G2Projective);
+
+impl Signature {
+    /// Decodes a compressed signature.
+    pub fn from_bytes(bytes: &[u8; 96]) -> Result<Self, AuthError> {
+        Option::<G2Affine>::from(G2Affine::from_compressed(bytes))
+            .map(|p| Self(p.into()))
+            .ok_or(AuthError::InvalidEncoding)
+    }
+
+    /// Returns the compressed signature.
+    pub fn to_bytes(&self) -> [u8; 96] {
+        self.0.to_affine().to_compressed()
+    }
+
+    /// Aggregates signatures into a single signature of the same size.
+    pub fn aggregate<'a>(signatures: impl IntoIterator<Item = &'a Self>) -> Self {
+        Self(signatures.into_iter().map(|s| s.0).sum())
+    }
+
+    /// Verifies an aggregate signature over pairs of public keys and messages, with a single
+    /// multi-pairing. All messages must be distinct, otherwise the aggregate is rejected.
+    pub fn verify_aggregate<'a>(
+        &self,
+        pairs: impl IntoIterator<Item = (&'a PublicKey, &'a [u8])>,
+    ) -> Result<(), AuthError> {
+        let mut messages = Vec::new();
+        let mut keys = Vec::new();
+        let mut hashes = Vec::new();
+
+        for (key, msg) in pairs {
+            if key.0.is_identity().into() || messages.contains(&msg) {
+                return Err(AuthError::InvalidSignature);
+            }
+
+            messages.push(msg);
+            keys.push(key.0.to_affine());
+            hashes.push(G2Prepared::from(hash_to_g2(msg).to_affine()));
+        }
+
+        if keys.is_empty() {
+            return Err(AuthError::InvalidSignature);
+        }
+
+        // e(g1, σ) == Πᵢ e(pkᵢ, H(mᵢ)) <=> e(-g1, σ) · Πᵢ e(pkᵢ, H(mᵢ)) == 1
+        let generator = -G1Affine::generator();
+        let signature = G2Prepared::from(self.0.to_affine());
+
+        let mut terms = keys.iter().zip(&hashes).collect::<Vec<_>>();
+        terms.push((&generator, &signature));
+
+        if Bls12::multi_miller_loop(&terms).final_exponentiation() != Gt::identity() {
+            return Err(AuthError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`GenerationHeader`] signed by the source of the generation, so receivers can check that the
+/// commitments came from the claimed source before trusting them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedHeader {
+    /// The generation header.
+    pub header: GenerationHeader,
+    /// The source's signature over [`GenerationHeader::to_bytes`].
+    pub signature: Signature,
+}
+
+impl SignedHeader {
+    /// Signs the header with the source's secret key.
+    pub fn sign(header: GenerationHeader, key: &SecretKey) -> Self {
+        let signature = key.sign(&header.to_bytes());
+        Self { header, signature }
+    }
+
+    /// Verifies that the header was signed by `key`.
+    pub fn verify(&self, key: &PublicKey) -> Result<(), AuthError> {
+        key.verify(&self.header.to_bytes(), &self.signature)
+    }
+
+    /// Aggregates the signatures of many signed headers (e.g. from different sources) into one.
+    pub fn aggregate(headers: &[Self]) -> Signature {
+        Signature::aggregate(headers.iter().map(|h| &h.signature))
+    }
+
+    /// Verifies an aggregate signature over headers signed by the given keys, see
+    /// [`Signature::verify_aggregate`].
+    pub fn verify_aggregate(
+        signature: &Signature,
+        headers: &[(PublicKey, &GenerationHeader)],
+    ) -> Result<(), AuthError> {
+        let messages = headers.iter().map(|(_, h)| h.to_bytes()).collect::<Vec<_>>();
+        signature
+            .verify_aggregate(headers.iter().zip(&messages).map(|((k, _), m)| (k, m.as_slice())))
+    }
+}
+
+/// Hashes a message to G2.
+fn hash_to_g2(msg: &[u8]) -> G2Projective {
+    G2Projective::hash_to_curve(msg, DST, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use zc_rlnc::{RLNCError, primitives::ChunksError};
+
+    use super::*;
+    use crate::authenticated::{AuthenticatedDecoder, AuthenticatedEncoder};
+
+    #[test]
+    fn test_sign_verify_header() {
+        let data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let key = SecretKey::random(rand::rng());
+        let public_key = PublicKey::from_bytes(&key.public_key().to_bytes()).unwrap();
+
+        let encoder = AuthenticatedEncoder::new(&data, 4, [3u8; 32]).unwrap();
+
+        let start = Instant::now();
+        let signed = encoder.signed_header(&key);
+        println!("Signing time: {:?}", start.elapsed());
+
+        let start = Instant::now();
+        signed.verify(&public_key).unwrap();
+        println!("Verification time: {:?}", start.elapsed());
+
+        // A different key or a modified header is rejected.
+        let other = SecretKey::random(rand::rng()).public_key();
+        assert!(matches!(signed.verify(&other), Err(AuthError::InvalidSignature)));
+        assert!(matches!(
+            AuthenticatedDecoder::from_signed(signed.clone(), &other),
+            Err(AuthError::InvalidSignature)
+        ));
+
+        let mut tampered = signed.clone();
+        tampered.header.seed = [4u8; 32];
+        assert!(tampered.verify(&public_key).is_err());
+
+        let signature = Signature::from_bytes(&signed.signature.to_bytes()).unwrap();
+        assert_eq!(signature, signed.signature);
+
+        // Signatures are compatible with the IETF basic scheme as implemented by `blst`.
+        let blst_signature = blst::min_pk::Signature::from_bytes(&signature.to_bytes()).unwrap();
+        let blst_key = blst::min_pk::PublicKey::from_bytes(&public_key.to_bytes()).unwrap();
+        assert_eq!(
+            blst_signature.verify(true, &signed.header.to_bytes(), DST, &[], &blst_key, true),
+            blst::BLST_ERROR::BLST_SUCCESS
+        );
+
+        // The derived layout fields aren't signed, but a decoder rejects them if they're changed.
+        let mut tampered = signed.clone();
+        tampered.header.layout.symbols_per_chunk = usize::MAX;
+        tampered.verify(&public_key).unwrap();
+        assert!(matches!(
+            AuthenticatedDecoder::from_signed(tampered, &public_key),
+            Err(AuthError::Coding(RLNCError::ChunksError(ChunksError::InvalidLayout)))
+        ));
+
+        let mut tampered = signed.clone();
+        tampered.header.layout.symbols_per_chunk -= 1;
+        assert!(AuthenticatedDecoder::from_signed(tampered, &public_key).is_err());
+
+        let mut decoder = AuthenticatedDecoder::from_signed(signed, &public_key).unwrap();
+        let decoded = loop {
+            if let Some(decoded) = decoder.decode(encoder.encode(rand::rng()).unwrap()).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_aggregate_headers() {
+        let sources = (0..8u8)
+            .map(|i| {
+                let data = rand::rng().random_iter().take(1024).collect::<Vec<_>>();
+                let key = SecretKey::from_ikm(&[i; 32]).unwrap();
+                let encoder = AuthenticatedEncoder::new(&data, 2, [i; 32]).unwrap();
+
+                (key.public_key(), encoder.signed_header(&key))
+            })
+            .collect::<Vec<_>>();
+
+        let signed = sources.iter().map(|(_, s)| s.clone()).collect::<Vec<_>>();
+        let signature = SignedHeader::aggregate(&signed);
+
+        let mut headers = sources.iter().map(|(k, s)| (*k, &s.header)).collect::<Vec<_>>();
+
+        let start = Instant::now();
+        SignedHeader::verify_aggregate(&signature, &headers).unwrap();
+        println!("Aggregate verification time ({} headers): {:?}", headers.len(), start.elapsed());
+
+        // Swapping keys, dropping a header or repeating a message is rejected.
+        let (first, second) = (headers[0].0, headers[1].0);
+        (headers[0].0, headers[1].0) = (second, first);
+        assert!(SignedHeader::verify_aggregate(&signature, &headers).is_err());
+        (headers[0].0, headers[1].0) = (first, second);
+
+        assert!(SignedHeader::verify_aggregate(&signature, &headers[1..]).is_err());
+
+        headers.push(headers[0]);
+        assert!(SignedHeader::verify_aggregate(&signature, &headers).is_err());
+    }
+}
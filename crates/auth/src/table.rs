@@ -0,0 +1,127 @@
+//! Precomputed fixed-base tables for multi-scalar multiplication over fixed generators.
+use blst::{
+    blst_p1_affine, blst_p1s_mult_wbits, blst_p1s_mult_wbits_precompute,
+    blst_p1s_mult_wbits_precompute_sizeof, blst_p1s_mult_wbits_scratch_sizeof, limb_t,
+};
+use blstrs::{G1Affine, G1Projective, Scalar};
+use group::Group;
+
+use crate::commit::MAX_WINDOW_BITS;
+
+/// The minimum number of generators handled by a single thread when committing in parallel.
+#[cfg(feature = "parallel")]
+const MIN_POINTS_PER_TASK: usize = 256;
+
+/// Windowed fixed-base tables for a set of generators, built with `blst`'s `wbits` routines.
+///
+/// For every generator, the table holds `2^(window_bits - 1)` precomputed multiples, which turns
+/// a multi-scalar multiplication into table lookups, additions and a single shared chain of
+/// doublings. Larger windows trade memory (`n * 2^(window_bits - 1) * 96` bytes for `n`
+/// generators) for speed.
+pub(crate) struct FixedBaseTable {
+    table: Vec<blst_p1_affine>,
+    window_bits: usize,
+    len: usize,
+}
+
+impl std::fmt::Debug for FixedBaseTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedBaseTable")
+            .field("window_bits", &self.window_bits)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FixedBaseTable {
+    /// Builds the tables for the given generators.
+    pub(crate) fn new(generators: &[G1Affine], window_bits: usize) -> Self {
+        assert!(
+            (1..=MAX_WINDOW_BITS).contains(&window_bits),
+            "window size must be between 1 and {MAX_WINDOW_BITS} bits"
+        );
+
+        let points = generators.iter().map(|g| g.as_ref() as *const _).collect::<Vec<_>>();
+
+        // SAFETY: the table is allocated with the size reported by `blst` for these parameters,
+        // and all point pointers are valid for the duration of the call.
+        let table = unsafe {
+            let size = blst_p1s_mult_wbits_precompute_sizeof(window_bits, points.len());
+            let mut table = vec![blst_p1_affine::default(); size / size_of::<blst_p1_affine>()];
+
+            blst_p1s_mult_wbits_precompute(
+                table.as_mut_ptr(),
+                window_bits,
+                points.as_ptr(),
+                points.len(),
+            );
+
+            table
+        };
+
+        Self { table, window_bits, len: generators.len() }
+    }
+
+    /// Returns the window size in bits.
+    pub(crate) const fn window_bits(&self) -> usize {
+        self.window_bits
+    }
+
+    /// Returns the size of the tables in bytes.
+    pub(crate) const fn size(&self) -> usize {
+        self.table.len() * size_of::<blst_p1_affine>()
+    }
+
+    /// Computes the multi-scalar multiplication of the generators with `scalars`.
+    pub(crate) fn multi_exp(&self, scalars: &[Scalar]) -> G1Projective {
+        assert_eq!(scalars.len(), self.len);
+
+        let scalars = scalars.iter().map(Scalar::to_bytes_le).collect::<Vec<_>>();
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            let per_task = self.len.div_ceil(rayon::current_num_threads()).max(MIN_POINTS_PER_TASK);
+            if per_task < self.len {
+                return scalars
+                    .par_chunks(per_task)
+                    .enumerate()
+                    .map(|(i, scalars)| self.multi_exp_range(i * per_task, scalars))
+                    .reduce(G1Projective::identity, |a, b| a + b);
+            }
+        }
+
+        self.multi_exp_range(0, &scalars)
+    }
+
+    /// Computes the multi-scalar multiplication of the generators starting at `offset` with the
+    /// little-endian encoded `scalars`.
+    fn multi_exp_range(&self, offset: usize, scalars: &[[u8; 32]]) -> G1Projective {
+        let rows = 1 << (self.window_bits - 1);
+        let table = &self.table[offset * rows..(offset + scalars.len()) * rows];
+        let scalars = scalars.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+
+        let mut result = G1Projective::identity();
+
+        // SAFETY: the table rows of each generator are stored contiguously, so `table` holds the
+        // rows for exactly `scalars.len()` generators. The scratch space is allocated with the
+        // size reported by `blst`.
+        unsafe {
+            let size = blst_p1s_mult_wbits_scratch_sizeof(scalars.len());
+            let mut scratch = vec![0 as limb_t; size / size_of::<limb_t>()];
+
+            blst_p1s_mult_wbits(
+                result.as_mut(),
+                table.as_ptr(),
+                self.window_bits,
+                scalars.len(),
+                scalars.as_ptr(),
+                255,
+                scratch.as_mut_ptr(),
+            );
+        }
+
+        result
+    }
+}
@@ -0,0 +1,149 @@
+//! Verification of coded packets against Pedersen commitments.
+use blstrs::{G1Projective, Scalar};
+use rand::Rng;
+use zc_rlnc::{RLNCError, primitives::packet::RLNCPacket};
+
+use crate::{
+    batch::BatchVerifier,
+    commit::{ChunkCommitments, PedersenCommitter},
+};
+
+/// Errors that can occur when authenticating coded packets.
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    /// Encoding or decoding failed.
+    #[error(transparent)]
+    Coding(#[from] RLNCError),
+    /// The coding vector length doesn't match the number of chunk commitments.
+    #[error("coding vector length must match commitment count: got {0}, expected {1}")]
+    InvalidCodingVectorLength(usize, usize),
+    /// The number of symbols doesn't match the number of generators.
+    #[error("symbol count must match generator count: got {0}, expected {1}")]
+    InvalidSymbolCount(usize, usize),
+    /// The number of MAC tags doesn't match the number of keys.
+    #[error("tag count must match key count: got {0}, expected {1}")]
+    InvalidTagCount(usize, usize),
+    /// The packet doesn't match the commitments, i.e. it has been tampered with.
+    #[error("packet doesn't match commitments")]
+    InvalidPacket,
+    /// Some packets in a batch don't match the commitments.
+    #[error("invalid packets at indices {0:?}")]
+    InvalidPackets(Vec<usize>),
+    /// The signature doesn't match the public key and message.
+    #[error("invalid signature")]
+    InvalidSignature,
+    /// The committer wasn't derived from the seed of the generation.
+    #[error("committer seed doesn't match the generation seed")]
+    SeedMismatch,
+    /// A key, signature, commitment or generator set couldn't be decoded.
+    #[error("invalid encoding")]
+    InvalidEncoding,
+}
+
+/// Verifies coded packets against the commitments of the original chunks, using the additive
+/// homomorphism of Pedersen commitments.
+///
+/// A coded packet with coding vector `c` and data `d` is valid if
+///
+/// ```text
+/// Σᵢ cᵢ · Cᵢ == commit(d)
+/// ```
+///
+/// where `Cᵢ` are the chunk commitments.
+#[derive(Debug)]
+pub struct Verifier {
+    committer: PedersenCommitter,
+    commitments: ChunkCommitments,
+}
+
+impl Verifier {
+    /// Creates a new verifier from the committer and the commitments of the original chunks.
+    pub const fn new(committer: PedersenCommitter, commitments: ChunkCommitments) -> Self {
+        Self { committer, commitments }
+    }
+
+    /// Returns the committer.
+    pub const fn committer(&self) -> &PedersenCommitter {
+        &self.committer
+    }
+
+    /// Returns the chunk commitments.
+    pub const fn commitments(&self) -> &ChunkCommitments {
+        &self.commitments
+    }
+
+    /// Verifies a single coded packet.
+    pub fn verify(&self, packet: &RLNCPacket<Scalar>) -> Result<(), AuthError> {
+        if packet.coding_vector.len() != self.commitments.len() {
+            return Err(AuthError::InvalidCodingVectorLength(
+                packet.coding_vector.len(),
+                self.commitments.len(),
+            ));
+        }
+
+        if packet.data.len() != self.committer.len() {
+            return Err(AuthError::InvalidSymbolCount(packet.data.len(), self.committer.len()));
+        }
+
+        let expected = G1Projective::multi_exp(self.commitments.inner(), &packet.coding_vector);
+        if expected != self.committer.commit(&packet.data) {
+            return Err(AuthError::InvalidPacket);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies many coded packets at once, see [`BatchVerifier`]. On failure, returns
+    /// [`AuthError::InvalidPackets`] with the indices of all invalid packets.
+    pub fn verify_batch<R: Rng>(
+        &self,
+        packets: &[RLNCPacket<Scalar>],
+        rng: R,
+    ) -> Result<(), AuthError> {
+        BatchVerifier::new(&self.committer, self.commitments.inner()).verify(packets, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use zc_rlnc::{decode::Decoder, encode::Encoder};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_packets() {
+        let data = rand::rng().random_iter().take(1024 * 64).collect::<Vec<_>>();
+        let chunk_count = 10;
+
+        let chunks = Encoder::<Scalar>::prepare(&data, chunk_count).unwrap();
+        let committer = PedersenCommitter::new([0u8; 32], chunks.layout().symbols_per_chunk);
+        let commitments = ChunkCommitments::new(&committer, &chunks).unwrap();
+        let verifier = Verifier::new(committer, commitments);
+
+        let encoder = Encoder::from_chunks(chunks);
+        let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+
+        let mut polluted = encoder.encode(rand::rng()).unwrap();
+        polluted.data[0] += Scalar::from(1u64);
+        assert!(matches!(verifier.verify(&polluted), Err(AuthError::InvalidPacket)));
+
+        polluted.coding_vector.pop();
+        assert!(matches!(
+            verifier.verify(&polluted),
+            Err(AuthError::InvalidCodingVectorLength(got, expected))
+                if got == chunk_count - 1 && expected == chunk_count
+        ));
+
+        let decoded = loop {
+            let packet = encoder.encode(rand::rng()).unwrap();
+            verifier.verify(&packet).unwrap();
+
+            if let Some(decoded) = decoder.decode(packet).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,780 @@
+//! The gossip broadcast state machine.
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IteratorRandom};
+use zc_rlnc::{
+    RLNCError,
+    decode::Decoder,
+    encode::Encoder,
+    primitives::{field::Field, packet::RLNCPacket},
+};
+
+use crate::message::{GenerationId, Message};
+
+/// Errors that can occur when broadcasting or handling messages.
+#[derive(Debug, thiserror::Error)]
+pub enum GossipError {
+    /// Encoding or decoding failed.
+    #[error(transparent)]
+    Coding(#[from] RLNCError),
+    /// A serialized message is malformed.
+    #[error("invalid message")]
+    InvalidMessage,
+    /// A broadcast with the same generation id is active or was seen recently.
+    #[error("duplicate generation")]
+    DuplicateGeneration,
+}
+
+/// The parameters of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GossipConfig {
+    /// The number of chunks (generation size) of broadcasts.
+    pub chunk_count: usize,
+    /// The number of random peers that every coded packet is sent to.
+    pub fanout: usize,
+    /// The maximum number of packets in flight to a peer, i.e. sent but not acknowledged with a
+    /// [`Message::Rank`]. Larger windows decode faster, but send more redundant packets. Losses
+    /// are made up for by retransmits rather than by sending extra packets up front.
+    pub window: usize,
+    /// The interval at which nodes send more packets to peers that aren't done, to make up for
+    /// losses.
+    pub retransmit_interval: Duration,
+    /// The time without progress after which a generation is dropped. Dropped generations are
+    /// remembered for as long again, so that late packets don't restart them.
+    pub timeout: Duration,
+    /// The maximum number of generations that are active or remembered. Any peer can start a
+    /// generation with a single packet, so packets of new generations are ignored at the limit,
+    /// which bounds the memory peers can make this node use.
+    pub max_generations: usize,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            chunk_count: 16,
+            fanout: 4,
+            window: 2,
+            retransmit_interval: Duration::from_millis(100),
+            timeout: Duration::from_secs(5),
+            max_generations: 1024,
+        }
+    }
+}
+
+/// A message to send to a peer.
+#[derive(Debug, Clone)]
+pub struct Transmit<P, F: Field> {
+    /// The peer to send the message to.
+    pub to: P,
+    /// The message.
+    pub message: Message<F>,
+}
+
+/// An event for the application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A broadcast was decoded.
+    Decoded {
+        /// The generation id of the broadcast.
+        generation: GenerationId,
+        /// The broadcast data.
+        data: Vec<u8>,
+    },
+    /// A broadcast was dropped before it could be decoded.
+    TimedOut {
+        /// The generation id of the broadcast.
+        generation: GenerationId,
+    },
+}
+
+/// How a node takes part in a generation.
+#[derive(Debug)]
+enum Coder<F: Field> {
+    /// The node started the broadcast.
+    Source(Encoder<F>),
+    /// The node receives the broadcast.
+    Receiver(Decoder<F>),
+}
+
+/// What a node knows about a peer in a generation.
+#[derive(Debug, Clone, Copy, Default)]
+struct PeerState {
+    /// The last rank the peer reported, or the chunk count if it's done.
+    rank: usize,
+    /// The number of packets sent to the peer and not acknowledged since the last retransmit
+    /// interval.
+    in_flight: usize,
+    /// This node's rank when the peer last reported a packet from it that wasn't innovative since
+    /// the last retransmit interval. The peer most likely has everything this node has, until
+    /// this node's rank grows.
+    saturated_at: Option<usize>,
+}
+
+/// The state of an active generation.
+#[derive(Debug)]
+struct Generation<F: Field, P> {
+    coder: Coder<F>,
+    chunk_count: usize,
+    chunk_size: u32,
+    peers: HashMap<P, PeerState>,
+    /// Whether this node has all the data, i.e. is the source or decoded.
+    complete: bool,
+    /// The time of the last innovative packet, or when the generation started.
+    last_progress: Instant,
+    next_retransmit: Instant,
+}
+
+impl<F: Field, P: Eq + Hash> Generation<F, P> {
+    /// Returns the number of linearly independent packets this node has.
+    const fn rank(&self) -> usize {
+        match &self.coder {
+            Coder::Source(_) => self.chunk_count,
+            Coder::Receiver(decoder) => decoder.rank(),
+        }
+    }
+
+    /// Returns true if the peer is done.
+    fn is_done(&self, peer: &P) -> bool {
+        self.peers.get(peer).is_some_and(|p| p.rank >= self.chunk_count)
+    }
+
+    /// Returns true if the peer may be sent another packet: it's missing more than the packets in
+    /// flight, it's not saturated at the current rank, and its window isn't full.
+    fn wants(&self, peer: &P, window: usize) -> bool {
+        let state = self.peers.get(peer).copied().unwrap_or_default();
+
+        state.rank + state.in_flight < self.chunk_count &&
+            state.saturated_at != Some(self.rank()) &&
+            state.in_flight < window
+    }
+
+    /// Returns a new coded packet, or `None` if the node has nothing to send yet.
+    fn packet<R: Rng>(&self, rng: &mut R) -> Option<RLNCPacket<F>> {
+        match &self.coder {
+            Coder::Source(encoder) => encoder.encode(rng).ok(),
+            Coder::Receiver(decoder) => decoder.recode(rng).ok(),
+        }
+    }
+
+    /// Returns the time at which the generation is dropped.
+    fn expires_at(&self, timeout: Duration) -> Instant {
+        self.last_progress + timeout
+    }
+}
+
+/// A generation that was dropped, kept to suppress duplicates.
+#[derive(Debug, Clone, Copy)]
+struct Tombstone {
+    /// Whether this node had all the data.
+    complete: bool,
+    expires_at: Instant,
+}
+
+/// A sans-IO state machine that broadcasts messages to, and receives broadcasts from, a set of
+/// peers identified by `P`.
+///
+/// Sources push `chunk_count` coded packets, each to `fanout` random peers. Every innovative
+/// packet a node receives is recoded and forwarded to `fanout` random peers other than the sender.
+/// Until the generation times out, nodes also send a packet to `fanout` peers every
+/// `retransmit_interval` to make up for losses. Only peers that want a packet are picked, see
+/// below.
+///
+/// Receivers acknowledge every packet with their rank in a [`Message::Rank`], and nodes that
+/// decoded send [`Message::Done`] to all peers instead. Senders use the feedback for rate control:
+/// they keep at most `window` packets in flight to a peer and no more than it's missing, refill
+/// the window whenever a peer acknowledges a packet that was innovative, and stop sending to a
+/// peer that reported a packet that wasn't, until their own rank grows or the next retransmit
+/// interval. Nobody sends packets to peers that are done.
+///
+/// The caller drives the state machine with [`Gossip::handle`] and [`Gossip::handle_timeout`],
+/// and drains it with [`Gossip::poll_transmit`] and [`Gossip::poll_event`].
+#[derive(Debug)]
+pub struct Gossip<F: Field, P> {
+    config: GossipConfig,
+    peers: Vec<P>,
+    generations: BTreeMap<GenerationId, Generation<F, P>>,
+    tombstones: BTreeMap<GenerationId, Tombstone>,
+    transmits: VecDeque<Transmit<P, F>>,
+    events: VecDeque<Event>,
+    rng: StdRng,
+}
+
+impl<F: Field, P: Copy + Eq + Hash + fmt::Debug> Gossip<F, P> {
+    /// Creates a new state machine with the given peers. The random number generator seeds the
+    /// generator used for coding and peer selection.
+    pub fn new<R: Rng>(config: GossipConfig, peers: Vec<P>, mut rng: R) -> Self {
+        Self {
+            config,
+            peers,
+            generations: BTreeMap::new(),
+            tombstones: BTreeMap::new(),
+            transmits: VecDeque::new(),
+            events: VecDeque::new(),
+            rng: StdRng::from_rng(&mut rng),
+        }
+    }
+
+    /// Returns the configuration.
+    pub const fn config(&self) -> &GossipConfig {
+        &self.config
+    }
+
+    /// Returns the peers.
+    pub fn peers(&self) -> &[P] {
+        &self.peers
+    }
+
+    /// Adds a peer, if it's not known yet.
+    pub fn add_peer(&mut self, peer: P) {
+        if !self.peers.contains(&peer) {
+            self.peers.push(peer);
+        }
+    }
+
+    /// Removes a peer.
+    pub fn remove_peer(&mut self, peer: P) {
+        self.peers.retain(|&p| p != peer);
+    }
+
+    /// Returns the rank of a generation, i.e. the chunk count for sources and the number of
+    /// linearly independent packets for receivers, or `None` if the generation isn't active.
+    pub fn rank(&self, generation: &GenerationId) -> Option<usize> {
+        self.generations.get(generation).map(Generation::rank)
+    }
+
+    /// Starts broadcasting data as a new generation.
+    pub fn broadcast(
+        &mut self,
+        generation: GenerationId,
+        data: &[u8],
+        now: Instant,
+    ) -> Result<(), GossipError> {
+        if self.generations.contains_key(&generation) || self.tombstones.contains_key(&generation) {
+            return Err(GossipError::DuplicateGeneration);
+        }
+
+        let encoder = Encoder::new(data, self.config.chunk_count)?;
+        let layout = encoder.layout();
+        self.generations.insert(
+            generation,
+            Generation {
+                coder: Coder::Source(encoder),
+                chunk_count: layout.chunk_count,
+                chunk_size: layout.chunk_size as u32,
+                peers: HashMap::new(),
+                complete: true,
+                last_progress: now,
+                next_retransmit: now + self.config.retransmit_interval,
+            },
+        );
+
+        for _ in 0..self.config.chunk_count {
+            self.push(&generation, None);
+        }
+
+        Ok(())
+    }
+
+    /// Handles a message received from a peer. Packets that don't match the layout of their
+    /// generation are ignored rather than returned as errors.
+    pub fn handle(
+        &mut self,
+        from: P,
+        message: Message<F>,
+        now: Instant,
+    ) -> Result<(), GossipError> {
+        match message {
+            Message::Packet { generation, chunk_size, packet } => {
+                self.handle_packet(from, generation, chunk_size, packet, now)
+            }
+            Message::Done { generation } => {
+                if let Some(state) = self.generations.get_mut(&generation) {
+                    state.peers.entry(from).or_default().rank = state.chunk_count;
+                }
+
+                Ok(())
+            }
+            Message::Rank { generation, rank } => {
+                let Some(state) = self.generations.get_mut(&generation) else {
+                    return Ok(());
+                };
+
+                let own_rank = state.rank();
+                let peer = state.peers.entry(from).or_default();
+                peer.in_flight = peer.in_flight.saturating_sub(1);
+
+                // The rank doesn't grow if the packet wasn't innovative, so this node has nothing
+                // more to give. Packets from other nodes can hide that, but only delay stopping.
+                if rank as usize > peer.rank {
+                    peer.rank = rank as usize;
+                    while self.send_packet(&generation, from) {}
+                } else {
+                    peer.saturated_at = Some(own_rank);
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_packet(
+        &mut self,
+        from: P,
+        generation: GenerationId,
+        chunk_size: u32,
+        packet: RLNCPacket<F>,
+        now: Instant,
+    ) -> Result<(), GossipError> {
+        if let Some(tombstone) = self.tombstones.get(&generation) {
+            if tombstone.complete {
+                self.send(from, Message::Done { generation });
+            }
+
+            return Ok(());
+        }
+
+        // Packets that don't match the layout of their generation are peer misbehaviour rather
+        // than errors of this node, so they're ignored, and can't start a generation either.
+        let rank = match self.generations.get_mut(&generation) {
+            Some(state) => {
+                let Generation {
+                    coder: Coder::Receiver(decoder),
+                    chunk_size: expected,
+                    complete: false,
+                    ..
+                } = state
+                else {
+                    // The sender doesn't know that this node is done.
+                    self.send(from, Message::Done { generation });
+                    return Ok(());
+                };
+
+                let rank = decoder.rank();
+                if chunk_size != *expected || decoder.push(packet).is_err() {
+                    return Ok(());
+                }
+
+                rank
+            }
+            None => {
+                if self.generations.len() + self.tombstones.len() >= self.config.max_generations {
+                    return Ok(());
+                }
+
+                let chunk_count = packet.coding_vector.len();
+                let Ok(mut decoder) = Decoder::new(chunk_size as usize, chunk_count) else {
+                    return Ok(());
+                };
+                if decoder.push(packet).is_err() {
+                    return Ok(());
+                }
+
+                self.generations.insert(
+                    generation,
+                    Generation {
+                        coder: Coder::Receiver(decoder),
+                        chunk_count,
+                        chunk_size,
+                        peers: HashMap::new(),
+                        complete: false,
+                        last_progress: now,
+                        next_retransmit: now + self.config.retransmit_interval,
+                    },
+                );
+
+                0
+            }
+        };
+
+        let state = self.generations.get_mut(&generation).expect("generation exists");
+        if state.rank() == rank {
+            self.send(from, Message::Rank { generation, rank: rank as u32 });
+            return Ok(());
+        }
+
+        state.last_progress = now;
+        if let Coder::Receiver(decoder) = &state.coder &&
+            decoder.can_decode()
+        {
+            let mut data = vec![0u8; decoder.decoded_len()?];
+            decoder.decode_into(&mut data)?;
+            state.complete = true;
+
+            self.events.push_back(Event::Decoded { generation, data });
+            for peer in self.peers.clone() {
+                self.send(peer, Message::Done { generation });
+            }
+        } else {
+            let rank = state.rank() as u32;
+            self.send(from, Message::Rank { generation, rank });
+        }
+
+        self.push(&generation, Some(from));
+
+        Ok(())
+    }
+
+    /// Handles timeouts: sends packets to peers that aren't done yet, and drops generations that
+    /// made no progress for too long. Call this when the time returned by
+    /// [`Gossip::poll_timeout`] is reached.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        let timeout = self.config.timeout;
+        self.tombstones.retain(|_, tombstone| tombstone.expires_at > now);
+
+        let ids = self.generations.keys().copied().collect::<Vec<_>>();
+        for generation in ids {
+            let state = &self.generations[&generation];
+            let finished = self.peers.iter().all(|peer| state.is_done(peer));
+
+            if state.expires_at(timeout) <= now || (state.complete && finished) {
+                let state = self.generations.remove(&generation).expect("generation exists");
+                if !state.complete {
+                    self.events.push_back(Event::TimedOut { generation });
+                }
+
+                self.tombstones.insert(
+                    generation,
+                    Tombstone { complete: state.complete, expires_at: now + timeout },
+                );
+            } else if state.next_retransmit <= now {
+                let state = self.generations.get_mut(&generation).expect("generation exists");
+                state.next_retransmit = now + self.config.retransmit_interval;
+
+                // Assume that unacknowledged packets were lost, and give saturated peers another
+                // chance in case the feedback was misleading, e.g. reordered.
+                for peer in state.peers.values_mut() {
+                    peer.in_flight = 0;
+                    peer.saturated_at = None;
+                }
+
+                self.push(&generation, None);
+            }
+        }
+    }
+
+    /// Returns the next time at which [`Gossip::handle_timeout`] should be called, if any.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        let generations = self
+            .generations
+            .values()
+            .map(|state| state.next_retransmit.min(state.expires_at(self.config.timeout)));
+
+        generations.chain(self.tombstones.values().map(|t| t.expires_at)).min()
+    }
+
+    /// Returns the next message to send.
+    pub fn poll_transmit(&mut self) -> Option<Transmit<P, F>> {
+        self.transmits.pop_front()
+    }
+
+    /// Returns the next event for the application.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Sends a new coded packet to `fanout` random peers that want one, except `exclude`.
+    fn push(&mut self, generation: &GenerationId, exclude: Option<P>) {
+        let Some(state) = self.generations.get(generation) else {
+            return;
+        };
+
+        let peers = self
+            .peers
+            .iter()
+            .filter(|&&peer| Some(peer) != exclude && state.wants(&peer, self.config.window))
+            .copied()
+            .choose_multiple(&mut self.rng, self.config.fanout);
+
+        for peer in peers {
+            self.send_packet(generation, peer);
+        }
+    }
+
+    /// Sends a new coded packet to a peer if it wants one, and returns true if it was sent.
+    fn send_packet(&mut self, generation: &GenerationId, peer: P) -> bool {
+        let Some(state) = self.generations.get_mut(generation) else {
+            return false;
+        };
+
+        if !state.wants(&peer, self.config.window) {
+            return false;
+        }
+
+        let Some(packet) = state.packet(&mut self.rng) else {
+            return false;
+        };
+
+        state.peers.entry(peer).or_default().in_flight += 1;
+
+        let message =
+            Message::Packet { generation: *generation, chunk_size: state.chunk_size, packet };
+        self.transmits.push_back(Transmit { to: peer, message });
+        true
+    }
+
+    fn send(&mut self, to: P, message: Message<F>) {
+        self.transmits.push_back(Transmit { to, message });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use zc_rlnc::primitives::field::Gf256;
+
+    use super::*;
+
+    /// The one-way delay between any two nodes.
+    const LATENCY: Duration = Duration::from_millis(10);
+
+    /// Runs a full mesh of nodes until no messages are in flight and no timeouts are pending,
+    /// dropping every message with probability `loss`. Messages go through the wire format.
+    /// Returns the events of every node and the number of packets sent.
+    fn run<R: Rng>(
+        nodes: &mut [Gossip<Gf256, usize>],
+        mut now: Instant,
+        loss: f64,
+        mut rng: R,
+    ) -> (Vec<Vec<Event>>, usize) {
+        let mut events = vec![Vec::new(); nodes.len()];
+        let mut packets = 0;
+
+        loop {
+            let mut in_flight = Vec::new();
+            for (from, node) in nodes.iter_mut().enumerate() {
+                while let Some(Transmit { to, message }) = node.poll_transmit() {
+                    packets += usize::from(matches!(message, Message::Packet { .. }));
+                    if !rng.random_bool(loss) {
+                        in_flight.push((from, to, message.to_bytes()));
+                    }
+                }
+
+                events[from].extend(std::iter::from_fn(|| node.poll_event()));
+            }
+
+            if in_flight.is_empty() {
+                let Some(next) = nodes.iter().filter_map(Gossip::poll_timeout).min() else {
+                    return (events, packets);
+                };
+
+                now = now.max(next);
+            } else {
+                now += LATENCY;
+                for (from, to, bytes) in in_flight {
+                    nodes[to].handle(from, Message::from_bytes(&bytes).unwrap(), now).unwrap();
+                }
+            }
+
+            for node in nodes.iter_mut() {
+                if node.poll_timeout().is_some_and(|t| t <= now) {
+                    node.handle_timeout(now);
+                }
+            }
+        }
+    }
+
+    fn mesh(n: usize, config: GossipConfig) -> Vec<Gossip<Gf256, usize>> {
+        (0..n)
+            .map(|i| {
+                let peers = (0..n).filter(|&p| p != i).collect();
+                Gossip::new(config, peers, StdRng::seed_from_u64(i as u64))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gossip_broadcast() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let data = (&mut rng).random_iter().take(16 * 1024).collect::<Vec<u8>>();
+        let generation = [1u8; 32];
+
+        let mut nodes = mesh(20, GossipConfig::default());
+        let start = Instant::now();
+        nodes[0].broadcast(generation, &data, start).unwrap();
+        assert!(matches!(
+            nodes[0].broadcast(generation, &data, start),
+            Err(GossipError::DuplicateGeneration)
+        ));
+
+        let (events, packets) = run(&mut nodes, start, 0.1, rng);
+        println!("{packets} packets for {} nodes", nodes.len());
+
+        assert!(events[0].is_empty());
+        for events in &events[1..] {
+            assert_eq!(events, &[Event::Decoded { generation, data: data.clone() }]);
+        }
+
+        // Everything was dropped after the timeout.
+        assert!(nodes.iter().all(|node| node.rank(&generation).is_none()));
+    }
+
+    #[test]
+    fn test_rank_feedback() {
+        let config = GossipConfig { chunk_count: 4, window: 2, ..Default::default() };
+        let mut source = Gossip::<Gf256, usize>::new(config, vec![1], StdRng::seed_from_u64(0));
+        let mut receiver = Gossip::<Gf256, usize>::new(config, vec![0], StdRng::seed_from_u64(1));
+
+        let start = Instant::now();
+        let generation = [5u8; 32];
+        source.broadcast(generation, &[9u8; 400], start).unwrap();
+
+        let exchange = |from: &mut Gossip<Gf256, usize>, to: &mut Gossip<Gf256, usize>| {
+            let messages = std::iter::from_fn(|| from.poll_transmit()).collect::<Vec<_>>();
+            for Transmit { to: peer, message } in &messages {
+                to.handle(1 - peer, message.clone(), start).unwrap();
+            }
+
+            messages.into_iter().map(|t| t.message).collect::<Vec<_>>()
+        };
+
+        // The window limits the packets in flight, and acknowledgements refill it.
+        let packets = exchange(&mut source, &mut receiver);
+        assert_eq!(packets.len(), 2);
+        let acks = exchange(&mut receiver, &mut source);
+        assert!(matches!(acks[..], [Message::Rank { rank: 1, .. }, Message::Rank { rank: 2, .. }]));
+        // The refilled packets get lost.
+        assert_eq!(std::iter::from_fn(|| source.poll_transmit()).count(), 2);
+
+        // A packet that isn't innovative stops the sender until the next retransmit interval.
+        receiver.handle(0, packets[0].clone(), start).unwrap();
+        let acks = exchange(&mut receiver, &mut source);
+        assert!(matches!(acks[..], [Message::Rank { rank: 2, .. }]));
+        assert!(source.poll_transmit().is_none());
+
+        source.handle_timeout(start + config.retransmit_interval);
+        assert_eq!(exchange(&mut source, &mut receiver).len(), 1);
+        assert!(matches!(
+            exchange(&mut receiver, &mut source)[..],
+            [Message::Rank { rank: 3, .. }]
+        ));
+        // Only as many packets as the receiver is missing are in flight.
+        assert_eq!(exchange(&mut source, &mut receiver).len(), 1);
+
+        // The receiver decoded, so it tells the source to stop.
+        assert!(matches!(receiver.poll_event(), Some(Event::Decoded { .. })));
+        let messages = exchange(&mut receiver, &mut source);
+        assert!(messages.iter().all(|m| matches!(m, Message::Done { .. })));
+        assert!(source.poll_transmit().is_none());
+        source.handle_timeout(start + config.retransmit_interval * 2);
+        assert_eq!(source.rank(&generation), None);
+    }
+
+    #[test]
+    fn test_mismatched_packets() {
+        let config = GossipConfig { chunk_count: 4, ..Default::default() };
+        let mut source = Gossip::<Gf256, usize>::new(config, vec![1], StdRng::seed_from_u64(0));
+        let mut receiver = Gossip::<Gf256, usize>::new(config, vec![0], StdRng::seed_from_u64(1));
+
+        let start = Instant::now();
+        let generation = [3u8; 32];
+        source.broadcast(generation, &[5u8; 100], start).unwrap();
+
+        let mut packets = std::iter::from_fn(|| source.poll_transmit()).map(|t| match t.message {
+            Message::Packet { chunk_size, packet, .. } => (chunk_size, packet),
+            message => panic!("unexpected message {message:?}"),
+        });
+        let (chunk_size, packet) = packets.next().unwrap();
+        let mismatched = |chunk_size: u32, packet: RLNCPacket<Gf256>| Message::Packet {
+            generation,
+            chunk_size: chunk_size + 1,
+            packet,
+        };
+
+        // A packet that doesn't match its own chunk size doesn't start the generation.
+        receiver.handle(0, mismatched(chunk_size, packet.clone()), start).unwrap();
+        assert_eq!(receiver.rank(&generation), None);
+        assert!(receiver.poll_transmit().is_none());
+
+        receiver.handle(0, Message::Packet { generation, chunk_size, packet }, start).unwrap();
+        assert_eq!(receiver.rank(&generation), Some(1));
+
+        // Packets that don't match the generation are ignored.
+        let (chunk_size, packet) = packets.next().unwrap();
+        receiver.handle(0, mismatched(chunk_size, packet), start).unwrap();
+        assert_eq!(receiver.rank(&generation), Some(1));
+    }
+
+    #[test]
+    fn test_generation_limit() {
+        let config = GossipConfig { chunk_count: 4, max_generations: 2, ..Default::default() };
+        let mut source = Gossip::<Gf256, usize>::new(config, vec![1], StdRng::seed_from_u64(0));
+        let mut receiver = Gossip::<Gf256, usize>::new(config, vec![0], StdRng::seed_from_u64(1));
+
+        let start = Instant::now();
+        let deliver = |source: &mut Gossip<Gf256, usize>,
+                       receiver: &mut Gossip<Gf256, usize>,
+                       generation: GenerationId,
+                       now: Instant| {
+            source.broadcast(generation, &[generation[0]; 100], now).unwrap();
+            while let Some(Transmit { message, .. }) = source.poll_transmit() {
+                receiver.handle(0, message, now).unwrap();
+            }
+        };
+
+        // Packets of a new generation are ignored once the limit is reached.
+        for i in 0..3u8 {
+            deliver(&mut source, &mut receiver, [i; 32], start);
+        }
+        assert_eq!(receiver.rank(&[1; 32]), Some(2));
+        assert_eq!(receiver.rank(&[2; 32]), None);
+
+        // Dropped generations still count until their tombstones expire.
+        let later = start + config.timeout;
+        receiver.handle_timeout(later);
+        assert_eq!(receiver.rank(&[0; 32]), None);
+        deliver(&mut source, &mut receiver, [3; 32], later);
+        assert_eq!(receiver.rank(&[3; 32]), None);
+
+        receiver.handle_timeout(later + config.timeout);
+        deliver(&mut source, &mut receiver, [4; 32], later + config.timeout);
+        assert_eq!(receiver.rank(&[4; 32]), Some(2));
+    }
+
+    #[test]
+    fn test_gossip_timeout_and_duplicates() {
+        let config = GossipConfig { chunk_count: 4, ..Default::default() };
+        let mut source = Gossip::<Gf256, usize>::new(config, vec![1], StdRng::seed_from_u64(0));
+        let mut receiver = Gossip::<Gf256, usize>::new(config, vec![0], StdRng::seed_from_u64(1));
+
+        let start = Instant::now();
+        let generation = [2u8; 32];
+        source.broadcast(generation, &[7u8; 1000], start).unwrap();
+        let packets = std::iter::from_fn(|| source.poll_transmit()).collect::<Vec<_>>();
+        assert_eq!(packets.len(), config.window);
+
+        // A single packet isn't enough, so the generation times out.
+        receiver.handle(0, packets[0].message.clone(), start).unwrap();
+        assert_eq!(receiver.rank(&generation), Some(1));
+        // The only peer is the sender, so nothing is forwarded, it's only acknowledged.
+        assert!(matches!(
+            std::iter::from_fn(|| receiver.poll_transmit()).map(|t| t.message).collect::<Vec<_>>()
+                [..],
+            [Message::Rank { rank: 1, .. }]
+        ));
+
+        let timeout = start + config.timeout;
+        receiver.handle_timeout(timeout);
+        assert_eq!(receiver.poll_event(), Some(Event::TimedOut { generation }));
+
+        // Late packets don't restart the generation until the tombstone expires.
+        receiver.handle(0, packets[1].message.clone(), timeout).unwrap();
+        assert_eq!(receiver.rank(&generation), None);
+        receiver.handle_timeout(timeout + config.timeout);
+        assert_eq!(receiver.poll_timeout(), None);
+
+        // A complete node tells senders that it's done.
+        let other = [3u8; 32];
+        source.broadcast(other, &[8u8; 1000], start).unwrap();
+        let packet = std::iter::from_fn(|| source.poll_transmit()).last().unwrap();
+        source.handle(1, packet.message, start).unwrap();
+        assert!(matches!(
+            source.poll_transmit().map(|t| t.message),
+            Some(Message::Done { generation }) if generation == other
+        ));
+    }
+}
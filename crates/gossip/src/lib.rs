@@ -0,0 +1,19 @@
+//! # `ZeroCast` Gossip
+//!
+//! A transport-agnostic (sans-IO) protocol for broadcasting messages to a set of peers with RLNC.
+//! Sources push coded packets, peers recode and forward them to a random fanout, and peers that
+//! decoded tell the others to stop sending. The [`Gossip`] state machine doesn't do any I/O: the
+//! caller feeds it received messages and timeouts, and sends the messages it produces.
+//!
+//! With the `tokio` feature, the `udp` module provides a simpler transport that sends objects as
+//! coded packets over UDP, without any feedback.
+
+pub mod gossip;
+pub mod message;
+#[cfg(feature = "tokio")]
+pub mod udp;
+
+pub use gossip::{Event, Gossip, GossipConfig, GossipError, Transmit};
+pub use message::{GenerationId, Message};
+#[cfg(feature = "tokio")]
+pub use udp::{UdpConfig, UdpError, UdpReceiver, UdpSender};
@@ -0,0 +1,165 @@
+//! Protocol messages and their wire format.
+use zc_rlnc::primitives::{field::Field, packet::RLNCPacket};
+
+use crate::gossip::GossipError;
+
+/// The identifier of a broadcast. Every broadcast is a single generation.
+pub type GenerationId = [u8; 32];
+
+/// The tag of a [`Message::Packet`].
+const PACKET_TAG: u8 = 0;
+/// The tag of a [`Message::Done`].
+const DONE_TAG: u8 = 1;
+/// The tag of a [`Message::Rank`].
+const RANK_TAG: u8 = 2;
+
+/// The size of the header of every message: the tag and the generation id.
+const HEADER_SIZE: usize = 1 + 32;
+
+/// The size of a serialized [`Message::Packet`] beyond the serialized packet: the header, chunk
+/// count and chunk size.
+pub const PACKET_OVERHEAD: usize = HEADER_SIZE + 8;
+
+/// A protocol message.
+#[derive(Debug, Clone)]
+pub enum Message<F: Field> {
+    /// A coded packet.
+    Packet {
+        /// The generation the packet belongs to.
+        generation: GenerationId,
+        /// The size of each chunk in bytes, so that receivers can create a decoder.
+        chunk_size: u32,
+        /// The coded packet.
+        packet: RLNCPacket<F>,
+    },
+    /// The sender decoded the generation and doesn't need more packets.
+    Done {
+        /// The generation the sender decoded.
+        generation: GenerationId,
+    },
+    /// Feedback that a packet from the receiver wasn't innovative, with the number of linearly
+    /// independent packets the sender has, so that the receiver can stop or throttle sending.
+    Rank {
+        /// The generation the feedback is about.
+        generation: GenerationId,
+        /// The rank of the sender's decoder.
+        rank: u32,
+    },
+}
+
+impl<F: Field> Message<F> {
+    /// Returns the generation the message belongs to.
+    pub const fn generation(&self) -> &GenerationId {
+        match self {
+            Self::Packet { generation, .. } |
+            Self::Done { generation } |
+            Self::Rank { generation, .. } => generation,
+        }
+    }
+
+    /// Serializes the message: a tag byte and the generation id, followed by the chunk count and
+    /// chunk size as little-endian `u32`s and the serialized packet for [`Message::Packet`], or
+    /// the rank as a little-endian `u32` for [`Message::Rank`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+
+        match self {
+            Self::Packet { generation, chunk_size, packet } => {
+                bytes.push(PACKET_TAG);
+                bytes.extend_from_slice(generation);
+                bytes.extend_from_slice(&(packet.coding_vector.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&chunk_size.to_le_bytes());
+                bytes.extend_from_slice(&packet.to_bytes());
+            }
+            Self::Done { generation } => {
+                bytes.push(DONE_TAG);
+                bytes.extend_from_slice(generation);
+            }
+            Self::Rank { generation, rank } => {
+                bytes.push(RANK_TAG);
+                bytes.extend_from_slice(generation);
+                bytes.extend_from_slice(&rank.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    /// Deserializes a message, see [`Message::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GossipError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(GossipError::InvalidMessage);
+        }
+
+        let (header, body) = bytes.split_at(HEADER_SIZE);
+        let generation = header[1..].try_into().expect("header is 33 bytes");
+
+        match header[0] {
+            PACKET_TAG if body.len() >= 8 => {
+                let (lengths, packet) = body.split_at(8);
+                let chunk_count = u32::from_le_bytes(lengths[..4].try_into().expect("4 bytes"));
+                let chunk_size = u32::from_le_bytes(lengths[4..].try_into().expect("4 bytes"));
+                let packet = RLNCPacket::from_bytes(packet, chunk_count as usize)?;
+
+                Ok(Self::Packet { generation, chunk_size, packet })
+            }
+            DONE_TAG if body.is_empty() => Ok(Self::Done { generation }),
+            RANK_TAG if body.len() == 4 => {
+                let rank = u32::from_le_bytes(body.try_into().expect("4 bytes"));
+                Ok(Self::Rank { generation, rank })
+            }
+            _ => Err(GossipError::InvalidMessage),
+        }
+    }
+
+    /// Returns the length of the serialized message.
+    pub const fn encoded_len(&self) -> usize {
+        match self {
+            Self::Packet { packet, .. } => {
+                PACKET_OVERHEAD + (packet.coding_vector.len() + packet.data.len()) * F::ENCODED_SIZE
+            }
+            Self::Done { .. } => HEADER_SIZE,
+            Self::Rank { .. } => HEADER_SIZE + 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zc_rlnc::primitives::field::Gf256;
+
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let packet =
+            RLNCPacket { coding_vector: vec![Gf256(1), Gf256(2)], data: vec![Gf256(3); 5] };
+        let message = Message::Packet { generation: [9; 32], chunk_size: 5, packet };
+
+        let bytes = message.to_bytes();
+        assert_eq!(bytes.len(), message.encoded_len());
+        let Message::Packet { generation, chunk_size, packet } =
+            Message::<Gf256>::from_bytes(&bytes).unwrap()
+        else {
+            panic!("expected a packet");
+        };
+        assert_eq!((generation, chunk_size), ([9; 32], 5));
+        assert_eq!(packet.coding_vector, [Gf256(1), Gf256(2)]);
+        assert_eq!(packet.data, [Gf256(3); 5]);
+
+        let done = Message::<Gf256>::Done { generation: [4; 32] }.to_bytes();
+        assert!(
+            matches!(Message::<Gf256>::from_bytes(&done), Ok(Message::Done { generation }) if generation == [4; 32])
+        );
+
+        let rank = Message::<Gf256>::Rank { generation: [5; 32], rank: 3 }.to_bytes();
+        assert!(matches!(
+            Message::<Gf256>::from_bytes(&rank),
+            Ok(Message::Rank { generation, rank: 3 }) if generation == [5; 32]
+        ));
+
+        assert!(Message::<Gf256>::from_bytes(&bytes[..20]).is_err());
+        assert!(Message::<Gf256>::from_bytes(&[done.as_slice(), &[0]].concat()).is_err());
+        assert!(Message::<Gf256>::from_bytes(&[&[7], &done[1..]].concat()).is_err());
+    }
+}
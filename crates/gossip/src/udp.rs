@@ -0,0 +1,354 @@
+//! A UDP transport on top of `tokio`, without feedback: senders split objects into coded packets
+//! and send more than the chunk count to make up for losses, and receivers decode them.
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::poll_fn,
+    io,
+    marker::PhantomData,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_core::Stream;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use tokio::{io::ReadBuf, net::UdpSocket};
+use zc_rlnc::{
+    RLNCError,
+    decode::Decoder,
+    encode::Encoder,
+    primitives::{field::Field, packet::RLNCPacket},
+};
+
+use crate::{
+    gossip::GossipError,
+    message::{GenerationId, Message, PACKET_OVERHEAD},
+};
+
+/// The maximum size of a UDP payload.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// The maximum number of generations a receiver decodes at the same time. The oldest is dropped
+/// when a new one arrives.
+const MAX_GENERATIONS: usize = 1024;
+
+/// The maximum number of decoded generations a receiver remembers, so that late packets don't
+/// start them again. These only take the size of their id, so many more are kept than decoders.
+const MAX_DECODED: usize = 64 * 1024;
+
+/// Errors of the UDP transport.
+#[derive(Debug, thiserror::Error)]
+pub enum UdpError {
+    /// Sending or receiving failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Encoding or decoding failed.
+    #[error(transparent)]
+    Coding(#[from] RLNCError),
+    /// A received datagram isn't a valid message.
+    #[error(transparent)]
+    Message(#[from] GossipError),
+}
+
+/// The parameters of a [`UdpSender`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UdpConfig {
+    /// The maximum size of a datagram in bytes. Objects are split into as few chunks as possible
+    /// such that every packet fits.
+    pub packet_size: usize,
+    /// The number of packets sent beyond the chunk count, as a fraction of the chunk count.
+    pub redundancy: f64,
+}
+
+impl Default for UdpConfig {
+    fn default() -> Self {
+        Self { packet_size: 1200, redundancy: 0.5 }
+    }
+}
+
+/// Sends objects as coded packets over UDP.
+#[derive(Debug)]
+pub struct UdpSender<F: Field> {
+    socket: UdpSocket,
+    config: UdpConfig,
+    rng: StdRng,
+    _field: PhantomData<F>,
+}
+
+impl<F: Field> UdpSender<F> {
+    /// Creates a new sender. The random number generator is seeded from `rng`.
+    pub fn new<R: Rng>(socket: UdpSocket, config: UdpConfig, mut rng: R) -> Self {
+        Self { socket, config, rng: StdRng::from_rng(&mut rng), _field: PhantomData }
+    }
+
+    /// Returns the socket.
+    pub const fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Splits an object into chunks and sends `chunk_count * (1 + redundancy)` coded packets of
+    /// it to `to`, rounded up. Returns the number of packets sent.
+    pub async fn send(
+        &mut self,
+        generation: GenerationId,
+        data: &[u8],
+        to: SocketAddr,
+    ) -> Result<usize, UdpError> {
+        let packet_size = self.config.packet_size.saturating_sub(PACKET_OVERHEAD);
+        let encoder = Encoder::<F>::with_packet_size(data, packet_size)?;
+        let layout = encoder.layout();
+
+        let extra = (layout.chunk_count as f64 * self.config.redundancy).ceil() as usize;
+        let count = layout.chunk_count + extra;
+        for _ in 0..count {
+            let packet = encoder.encode(&mut self.rng)?;
+            let chunk_size = layout.chunk_size as u32;
+            let bytes = Message::Packet { generation, chunk_size, packet }.to_bytes();
+
+            self.socket.send_to(&bytes, to).await?;
+        }
+
+        Ok(count)
+    }
+}
+
+/// Receives coded packets over UDP, and yields the objects it decodes as a [`Stream`] of
+/// generation ids and data. Every object is yielded once, unless its packets keep arriving after
+/// 65536 more objects were decoded. The stream never ends, and errors about single datagrams don't
+/// end it either.
+#[derive(Debug)]
+pub struct UdpReceiver<F: Field> {
+    socket: UdpSocket,
+    /// The generations being decoded, by id, chunk count and chunk size, so that packets with
+    /// another layout can't take over a generation.
+    decoders: HashMap<(GenerationId, usize, u32), Decoder<F>>,
+    /// The keys of `decoders`, oldest first.
+    generations: VecDeque<(GenerationId, usize, u32)>,
+    decoded: HashSet<GenerationId>,
+    /// The generations in `decoded`, oldest first.
+    decoded_order: VecDeque<GenerationId>,
+    buf: Vec<u8>,
+}
+
+impl<F: Field> UdpReceiver<F> {
+    /// Creates a new receiver.
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            decoders: HashMap::new(),
+            generations: VecDeque::new(),
+            decoded: HashSet::new(),
+            decoded_order: VecDeque::new(),
+            buf: vec![0u8; MAX_DATAGRAM_SIZE],
+        }
+    }
+
+    /// Returns the socket.
+    pub const fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Receives the next decoded object.
+    pub async fn recv(&mut self) -> Result<(GenerationId, Vec<u8>), UdpError> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Polls for the next decoded object.
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(GenerationId, Vec<u8>), UdpError>> {
+        loop {
+            let mut buf = ReadBuf::new(&mut self.buf);
+            ready!(self.socket.poll_recv_from(cx, &mut buf))?;
+
+            // Other messages need feedback, which this transport doesn't have.
+            if let Message::Packet { generation, chunk_size, packet } =
+                Message::from_bytes(buf.filled())? &&
+                let Some(data) = self.handle_packet(generation, chunk_size, packet)?
+            {
+                return Poll::Ready(Ok((generation, data)));
+            }
+        }
+    }
+
+    /// Adds a packet to the decoder of its generation, and returns the data if it decoded.
+    fn handle_packet(
+        &mut self,
+        generation: GenerationId,
+        chunk_size: u32,
+        packet: RLNCPacket<F>,
+    ) -> Result<Option<Vec<u8>>, UdpError> {
+        if self.decoded.contains(&generation) {
+            return Ok(None);
+        }
+
+        let key = (generation, packet.coding_vector.len(), chunk_size);
+        if let Some(decoder) = self.decoders.get_mut(&key) {
+            decoder.push(packet)?;
+        } else {
+            // Invalid packets must not leave a decoder behind.
+            let mut decoder = Decoder::new(chunk_size as usize, key.1)?;
+            decoder.push(packet)?;
+
+            if self.generations.len() == MAX_GENERATIONS &&
+                let Some(oldest) = self.generations.pop_front()
+            {
+                self.decoders.remove(&oldest);
+            }
+
+            self.decoders.insert(key, decoder);
+            self.generations.push_back(key);
+        }
+
+        if !self.decoders[&key].can_decode() {
+            return Ok(None);
+        }
+
+        let decoder = self.decoders.remove(&key).expect("decoder exists");
+        self.decoders.retain(|other, _| other.0 != generation);
+        self.generations.retain(|other| other.0 != generation);
+
+        if self.decoded_order.len() == MAX_DECODED &&
+            let Some(oldest) = self.decoded_order.pop_front()
+        {
+            self.decoded.remove(&oldest);
+        }
+        self.decoded.insert(generation);
+        self.decoded_order.push_back(generation);
+
+        let mut data = vec![0u8; decoder.decoded_len()?];
+        decoder.decode_into(&mut data)?;
+        Ok(Some(data))
+    }
+}
+
+// The receiver doesn't reference itself, so it can be moved even if the field type isn't `Unpin`.
+impl<F: Field> Unpin for UdpReceiver<F> {}
+
+impl<F: Field> Stream for UdpReceiver<F> {
+    type Item = Result<(GenerationId, Vec<u8>), UdpError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+    use zc_rlnc::primitives::field::Gf256;
+
+    use super::*;
+
+    /// Binds a socket to a random port on the loopback interface.
+    async fn bind() -> UdpSocket {
+        UdpSocket::bind("127.0.0.1:0").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_udp_transport_with_loss() {
+        let mut receiver = UdpReceiver::<Gf256>::new(bind().await);
+        let receiver_addr = receiver.socket().local_addr().unwrap();
+
+        // Forwards datagrams to the receiver, and drops 20% of them.
+        let proxy = bind().await;
+        let proxy_addr = proxy.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut rng = StdRng::seed_from_u64(0);
+            let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+            loop {
+                let (len, _) = proxy.recv_from(&mut buf).await.unwrap();
+                if rng.random_bool(0.8) {
+                    proxy.send_to(&buf[..len], receiver_addr).await.unwrap();
+                }
+            }
+        });
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut sender = UdpSender::<Gf256>::new(bind().await, UdpConfig::default(), &mut rng);
+
+        for i in 0..3u8 {
+            let generation = [i; 32];
+            let data = (&mut rng).random_iter().take(20_000 + i as usize).collect::<Vec<u8>>();
+
+            let sent = sender.send(generation, &data, proxy_addr).await.unwrap();
+            println!("sent {sent} packets for {} bytes", data.len());
+
+            // Late packets of the previous object are ignored.
+            let object = timeout(Duration::from_secs(5), receiver.recv()).await.unwrap().unwrap();
+            assert_eq!(object, (generation, data));
+        }
+
+        // Invalid datagrams yield an error, but don't end the stream.
+        sender.socket().send_to(&[1, 2, 3], receiver_addr).await.unwrap();
+        let item = poll_fn(|cx| Pin::new(&mut receiver).poll_next(cx)).await;
+        assert!(matches!(item, Some(Err(UdpError::Message(GossipError::InvalidMessage)))));
+
+        let data = vec![7u8; 5000];
+        sender.send([9; 32], &data, receiver_addr).await.unwrap();
+        let item = poll_fn(|cx| Pin::new(&mut receiver).poll_next(cx)).await;
+        assert_eq!(item.unwrap().unwrap(), ([9; 32], data));
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_layouts() {
+        let mut receiver = UdpReceiver::<Gf256>::new(bind().await);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let data = vec![3u8; 1000];
+        let encoder = Encoder::<Gf256>::new(&data, 4).unwrap();
+        let chunk_size = encoder.layout().chunk_size as u32;
+
+        // A packet that doesn't match its own chunk size is rejected without creating a decoder.
+        let packet = encoder.encode(&mut rng).unwrap();
+        assert!(receiver.handle_packet([0; 32], chunk_size + 1, packet).is_err());
+        assert!(receiver.decoders.is_empty());
+
+        // A packet with another layout doesn't take over the generation.
+        let other = Encoder::<Gf256>::new([4u8; 1000], 8).unwrap();
+        let other_size = other.layout().chunk_size as u32;
+        let packet = other.encode(&mut rng).unwrap();
+        assert_eq!(receiver.handle_packet([0; 32], other_size, packet).unwrap(), None);
+
+        let decoded = loop {
+            let packet = encoder.encode(&mut rng).unwrap();
+            if let Some(decoded) = receiver.handle_packet([0; 32], chunk_size, packet).unwrap() {
+                break decoded;
+            }
+        };
+        assert_eq!(decoded, data);
+        assert!(receiver.decoders.is_empty());
+        assert!(receiver.generations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decoded_outlive_decoders() {
+        let mut receiver = UdpReceiver::<Gf256>::new(bind().await);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let encoder = Encoder::<Gf256>::new([5u8; 1000], 4).unwrap();
+        let chunk_size = encoder.layout().chunk_size as u32;
+        let mut packet = || encoder.encode(&mut rng).unwrap();
+
+        let mut decoded = None;
+        while decoded.is_none() {
+            decoded = receiver.handle_packet([0; 32], chunk_size, packet()).unwrap();
+        }
+        assert_eq!(decoded.unwrap(), vec![5u8; 1000]);
+
+        // Pending generations push out each other's decoders, but not the decoded id.
+        for i in 1..=MAX_GENERATIONS as u16 {
+            let mut generation = [0; 32];
+            generation[..2].copy_from_slice(&i.to_le_bytes());
+            receiver.handle_packet(generation, chunk_size, packet()).unwrap();
+        }
+        assert_eq!(receiver.decoders.len(), MAX_GENERATIONS);
+
+        for _ in 0..4 {
+            assert_eq!(receiver.handle_packet([0; 32], chunk_size, packet()).unwrap(), None);
+        }
+    }
+}
@@ -2,20 +2,36 @@ use thiserror::Error;
 
 use crate::primitives::ChunksError;
 
+/// Errors that can occur when encoding or decoding.
 #[derive(Error, Debug)]
 pub enum RLNCError {
+    /// Preparing the chunks failed.
     #[error(transparent)]
     ChunksError(#[from] ChunksError),
+    /// The generation size is zero.
     #[error("Required packet count must be greater than 0")]
     ZeroPacketCount,
+    /// The chunk size doesn't match.
     #[error("Chunk size mismatch: got {0}, expected {1}")]
     ChunkSizeMismatch(usize, usize),
+    /// The coding vector length doesn't match the chunk count.
     #[error("Coding vector length must match chunk count: got {0}, expected {1}")]
     InvalidCodingVectorLength(usize, usize),
+    /// The number of symbols in a packet doesn't match the chunk layout.
+    #[error("Packet symbol count must match chunk layout: got {0}, expected {1}")]
+    InvalidSymbolCount(usize, usize),
+    /// The decoded data has no valid boundary marker.
     #[error("Invalid encoding")]
     InvalidEncoding,
+    /// A serialized packet is malformed.
+    #[error("Invalid packet encoding")]
+    InvalidPacketEncoding,
+    /// The decoder doesn't have full rank yet.
     #[error("Not enough linearly independent packets to decode, have {0}, need {1}")]
     NotEnoughPackets(usize, usize),
+    /// The output buffer can't hold the decoded data.
+    #[error("Output buffer too small: got {0} bytes, need {1}")]
+    BufferTooSmall(usize, usize),
 }
 
 /// The boundary marker is a special byte that is used to separate the encoded data from the
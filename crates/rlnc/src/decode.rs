@@ -1,9 +1,17 @@
 //! Module that implements the RLNC decoding algorithm.
 
+use rand::Rng;
+
 use crate::{
     common::RLNCError,
+    encode::random_coefficient,
     matrix::Matrix,
-    primitives::{ChunksError, field::Field, packet::RLNCPacket},
+    primitives::{
+        ChunkLayout, ChunksError,
+        field::Field,
+        packet::{DerivedPacket, RLNCPacket},
+        packing::Packing,
+    },
 };
 
 /// RLNC Decoder.
@@ -14,14 +22,35 @@ pub struct Decoder<F: Field> {
     /// The number of coded packets required to decode the original data, also known as the
     /// generation size.
     chunk_count: usize,
+    /// How the bytes of each chunk are packed into symbols.
+    packing: Packing,
+    /// The number of symbols in each coded packet.
+    symbols_per_chunk: usize,
 
     /// The RREF matrix of received coded packets.
     matrix: Matrix<F>,
 }
 
 impl<F: Field> Decoder<F> {
-    /// Creates a new decoder for the given chunk size and chunk count (generation size).
+    /// Creates a new decoder for the given chunk size and chunk count (generation size), for
+    /// chunks packed with [`Packing::Bytes`].
     pub fn new(chunk_size: usize, chunk_count: usize) -> Result<Self, RLNCError> {
+        Self::with_packing(chunk_size, chunk_count, Packing::Bytes)
+    }
+
+    /// Creates a new decoder for the given chunk layout, see [`Encoder::layout`].
+    ///
+    /// [`Encoder::layout`]: crate::encode::Encoder::layout
+    pub fn from_layout(layout: ChunkLayout) -> Result<Self, RLNCError> {
+        Self::with_packing(layout.chunk_size, layout.chunk_count, layout.packing)
+    }
+
+    /// Creates a new decoder for the given chunk size, chunk count (generation size) and packing.
+    pub fn with_packing(
+        chunk_size: usize,
+        chunk_count: usize,
+        packing: Packing,
+    ) -> Result<Self, RLNCError> {
         if chunk_size == 0 {
             return Err(ChunksError::ZeroChunkSize.into());
         }
@@ -30,12 +59,106 @@ impl<F: Field> Decoder<F> {
             return Err(RLNCError::ZeroPacketCount);
         }
 
-        Ok(Self { chunk_size, chunk_count, matrix: Matrix::new(chunk_count) })
+        // Chunks are always rounded up to fill their symbols.
+        let chunk_size = packing.round_chunk_size::<F>(chunk_size);
+        let symbols_per_chunk = packing.symbols_per_chunk::<F>(chunk_size);
+
+        Ok(Self {
+            chunk_size,
+            chunk_count,
+            packing,
+            symbols_per_chunk,
+            matrix: Matrix::new(chunk_count),
+        })
     }
 
     /// Decodes a coded packet. If the decoder has enough linearly independent packets, it will
     /// return the original data.
     pub fn decode(&mut self, packet: RLNCPacket<F>) -> Result<Option<Vec<u8>>, RLNCError> {
+        self.check_packet(&packet)?;
+
+        if self.matrix.push_rref(packet) {
+            return Ok(Some(self.matrix.decode(self.chunk_size, self.packing)?));
+        }
+
+        // Store the packet data separately - we need coding vectors and data separate
+        Ok(None)
+    }
+
+    /// Decodes a packet with a derived coding vector, see [`Encoder::encode_derived`]. The coding
+    /// vector is derived from the packet's public key and index, and the generation id.
+    ///
+    /// [`Encoder::encode_derived`]: crate::encode::Encoder::encode_derived
+    pub fn decode_derived(
+        &mut self,
+        generation_id: &[u8; 32],
+        packet: DerivedPacket<F>,
+    ) -> Result<Option<Vec<u8>>, RLNCError> {
+        self.decode(packet.into_packet(generation_id, self.chunk_count))
+    }
+
+    /// Adds a coded packet to the decoder without decoding. Returns true if the decoder has enough
+    /// linearly independent packets to decode the original data, which can then be retrieved
+    /// with [`Decoder::decode_into`].
+    pub fn push(&mut self, packet: RLNCPacket<F>) -> Result<bool, RLNCError> {
+        self.check_packet(&packet)?;
+        self.matrix.push_rref(packet);
+
+        Ok(self.can_decode())
+    }
+
+    /// Writes the original data into the caller-provided buffer, returning the number of bytes
+    /// written (i.e. the length of the original data). The buffer must be at least
+    /// [`Decoder::decoded_len`] bytes long.
+    ///
+    /// Unlike [`Decoder::decode`], this doesn't allocate.
+    pub fn decode_into(&self, out: &mut [u8]) -> Result<usize, RLNCError> {
+        self.matrix.decode_into(self.chunk_size, self.packing, out)
+    }
+
+    /// Appends the original data to the given buffer, returning the number of bytes written.
+    #[cfg(feature = "bytes")]
+    pub fn decode_into_buf<B: bytes::BufMut>(&self, buf: &mut B) -> Result<usize, RLNCError> {
+        let len = self.matrix.decoded_len(self.chunk_size, self.packing)?;
+        if buf.remaining_mut() < len {
+            return Err(RLNCError::BufferTooSmall(buf.remaining_mut(), len));
+        }
+
+        self.matrix.write_decoded(self.chunk_size, self.packing, len, |bytes| buf.put_slice(bytes));
+
+        Ok(len)
+    }
+
+    /// Recodes the packets received so far into a new coded packet with a random linear
+    /// combination, using the provided random number generator. This lets relays forward
+    /// innovative packets before they can decode.
+    pub fn recode<R: Rng>(&self, mut rng: R) -> Result<RLNCPacket<F>, RLNCError> {
+        let rows = self.matrix.rows();
+        if rows.is_empty() {
+            return Err(RLNCError::NotEnoughPackets(0, 1));
+        }
+
+        let mut packet = RLNCPacket {
+            coding_vector: vec![F::ZERO; self.chunk_count],
+            data: vec![F::ZERO; self.symbols_per_chunk],
+        };
+
+        for row in rows {
+            let coefficient = random_coefficient::<F, _>(&mut rng);
+            packet.subtract_row(row, -coefficient);
+        }
+
+        Ok(packet)
+    }
+
+    /// Returns the length of the original data. Fails if the decoder doesn't have enough linearly
+    /// independent packets yet.
+    pub fn decoded_len(&self) -> Result<usize, RLNCError> {
+        self.matrix.decoded_len(self.chunk_size, self.packing)
+    }
+
+    /// Checks that the packet matches the decoder's generation size and chunk layout.
+    const fn check_packet(&self, packet: &RLNCPacket<F>) -> Result<(), RLNCError> {
         if packet.coding_vector.len() != self.chunk_count {
             return Err(RLNCError::InvalidCodingVectorLength(
                 packet.coding_vector.len(),
@@ -43,12 +166,11 @@ impl<F: Field> Decoder<F> {
             ));
         }
 
-        if self.matrix.push_rref(packet) {
-            return Ok(Some(self.matrix.decode(self.chunk_size)?));
+        if packet.data.len() != self.symbols_per_chunk {
+            return Err(RLNCError::InvalidSymbolCount(packet.data.len(), self.symbols_per_chunk));
         }
 
-        // Store the packet data separately - we need coding vectors and data separate
-        Ok(None)
+        Ok(())
     }
 
     /// Returns the number of linearly independent packets received.
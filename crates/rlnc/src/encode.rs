@@ -3,9 +3,22 @@ use rand::Rng;
 
 use crate::{
     common::RLNCError,
-    primitives::{Chunks, field::Field, packet::RLNCPacket},
+    primitives::{
+        ChunkLayout, Chunks,
+        field::Field,
+        packet::{DerivedPacket, RLNCPacket, derive_coding_vector},
+    },
 };
 
+/// The number of symbol columns processed at once by [`Encoder::encode_batch_with_vectors`]. A
+/// block of 64 BLS12-381 scalars is 2KiB, so a chunk block comfortably stays in L1 cache while it's
+/// applied to every packet in the batch.
+const BATCH_BLOCK_SYMBOLS: usize = 64;
+
+/// The number of packets computed by each task in a parallel batch encoding.
+#[cfg(feature = "parallel")]
+const BATCH_ROWS_PER_TASK: usize = 4;
+
 /// RLNC encoder that's generic over the [`Field`] type. An ancoder should be instantiated
 /// per piece of data the caller wants to encode, then used to generate the encoded chunks.
 #[derive(Debug)]
@@ -28,8 +41,16 @@ impl<F: Field> Encoder<F> {
         Ok(Self { chunks, chunk_count, chunk_size })
     }
 
+    /// Creates a new encoder for the given data, choosing the chunk count such that every coded
+    /// packet fits into `packet_size` bytes. See [`ChunkLayout::for_packet_size`].
+    pub fn with_packet_size(data: impl AsRef<[u8]>, packet_size: usize) -> Result<Self, RLNCError> {
+        let chunks = Chunks::with_packet_size(data.as_ref(), packet_size)?;
+
+        Ok(Self::from_chunks(chunks))
+    }
+
     /// Creates a new encoder from a vector of chunks.
-    pub fn from_chunks(chunks: Chunks<F>) -> Self {
+    pub const fn from_chunks(chunks: Chunks<F>) -> Self {
         let chunk_count = chunks.len();
         let chunk_size = chunks.chunk_size();
 
@@ -41,7 +62,7 @@ impl<F: Field> Encoder<F> {
     /// This is determined by the chunk count (collection size), chunk size (work unit size), and
     /// the number of threads.
     #[cfg(feature = "parallel")]
-    fn should_parallelize(&self) -> bool {
+    const fn should_parallelize(&self) -> bool {
         // Min total work: 512KiB
         let min_total_work = 1024 * 512;
         // Min chunks: 2
@@ -58,7 +79,7 @@ impl<F: Field> Encoder<F> {
 
     /// Sequentially encodes the data with the given coding vector using linear combinations.
     fn encode_inner(&self, coding_vector: &[F]) -> Vec<F> {
-        let mut result = vec![F::ZERO; self.chunk_size.div_ceil(F::SAFE_CAPACITY)];
+        let mut result = vec![F::ZERO; self.chunks.layout().symbols_per_chunk];
 
         for (chunk, &coefficient) in self.chunks.inner().iter().zip(coding_vector) {
             if coefficient.is_zero_vartime() {
@@ -74,15 +95,20 @@ impl<F: Field> Encoder<F> {
     }
 
     /// Returns the number of chunks in the encoder.
-    pub fn chunk_count(&self) -> usize {
+    pub const fn chunk_count(&self) -> usize {
         self.chunk_count
     }
 
     /// Returns the size of each chunk in the encoder.
-    pub fn chunk_size(&self) -> usize {
+    pub const fn chunk_size(&self) -> usize {
         self.chunk_size
     }
 
+    /// Returns the layout of the chunks and the resulting coded packets.
+    pub const fn layout(&self) -> ChunkLayout {
+        self.chunks.layout()
+    }
+
     /// Prepares the data for encoding by splitting it into equally sized chunks and padding with
     /// zeros. Also converts the data into symbols in the chosen finite field.
     pub fn prepare(data: impl AsRef<[u8]>, chunk_count: usize) -> Result<Chunks<F>, RLNCError> {
@@ -122,8 +148,6 @@ impl<F: Field> Encoder<F> {
             return Err(RLNCError::InvalidCodingVectorLength(coding_vector.len(), self.chunk_count));
         }
 
-        let symbol_count = self.chunk_size.div_ceil(F::SAFE_CAPACITY);
-
         // Compute the encoded result either sequentially or in parallel, depending on the
         // enabled feature flag. We avoid sharing mutable state across threads by letting each
         // worker produce a partial vector and then combining (reducing) the partial results.
@@ -131,9 +155,9 @@ impl<F: Field> Encoder<F> {
         let result = {
             use rayon::prelude::*;
 
-            if !self.should_parallelize() {
-                self.encode_inner(coding_vector)
-            } else {
+            let symbol_count = self.chunks.layout().symbols_per_chunk;
+
+            if self.should_parallelize() {
                 // Map each (chunk, coefficient) pair to its contribution and then reduce all
                 // contributions into the final result.
                 self.chunks
@@ -148,7 +172,7 @@ impl<F: Field> Encoder<F> {
 
                         let mut acc = Vec::with_capacity(symbol_count);
 
-                        for symbol in chunk.symbols().iter() {
+                        for symbol in chunk.symbols() {
                             acc.push(*symbol * coefficient);
                         }
 
@@ -162,6 +186,8 @@ impl<F: Field> Encoder<F> {
                             a
                         },
                     )
+            } else {
+                self.encode_inner(coding_vector)
             }
         };
 
@@ -173,14 +199,121 @@ impl<F: Field> Encoder<F> {
 
     /// Encodes the data with a random coding vector, using the provided random number generator.
     pub fn encode<R: Rng>(&self, mut rng: R) -> Result<RLNCPacket<F>, RLNCError> {
-        let coding_vector: Vec<F> = (0..self.chunk_count)
-            .map(|_| {
-                let mut bytes = [0u8; 32];
-                rng.fill(&mut bytes[..F::SAFE_CAPACITY]);
-                F::from_bytes(&bytes)
-            })
-            .collect();
+        let coding_vector = self.random_coding_vector(&mut rng);
 
         self.encode_with_vector(&coding_vector)
     }
+
+    /// Encodes the data with the coding vector derived from the sender's public key, the
+    /// generation id and the packet index, see [`derive_coding_vector`]. The resulting packet
+    /// doesn't carry the coding vector, and is decoded with [`Decoder::decode_derived`].
+    ///
+    /// [`Decoder::decode_derived`]: crate::decode::Decoder::decode_derived
+    pub fn encode_derived(
+        &self,
+        public_key: &[u8],
+        generation_id: &[u8; 32],
+        index: u64,
+    ) -> Result<DerivedPacket<F>, RLNCError> {
+        let coding_vector =
+            derive_coding_vector(public_key, generation_id, index, self.chunk_count);
+        let packet = self.encode_with_vector(&coding_vector)?;
+
+        Ok(DerivedPacket { public_key: public_key.to_vec(), index, data: packet.data })
+    }
+
+    /// Encodes `count` packets with random coding vectors at once, using the provided random
+    /// number generator. See [`Encoder::encode_batch_with_vectors`].
+    pub fn encode_batch<R: Rng>(
+        &self,
+        mut rng: R,
+        count: usize,
+    ) -> Result<Vec<RLNCPacket<F>>, RLNCError> {
+        let coding_vectors =
+            (0..count).map(|_| self.random_coding_vector(&mut rng)).collect::<Vec<_>>();
+
+        self.encode_batch_with_vectors(&coding_vectors)
+    }
+
+    /// Encodes one packet per coding vector at once.
+    ///
+    /// Encoding `m` packets is the matrix product of the (m × k) coding matrix with the (k × n)
+    /// matrix of chunk symbols. Instead of `m` independent passes over all chunks (as with
+    /// repeated calls to [`Encoder::encode_with_vector`]), the product is computed in blocks of
+    /// symbol columns, so that each block of a chunk is loaded into cache once and applied to all
+    /// packets. With the `parallel` feature, groups of packets are computed on different threads.
+    pub fn encode_batch_with_vectors(
+        &self,
+        coding_vectors: &[Vec<F>],
+    ) -> Result<Vec<RLNCPacket<F>>, RLNCError> {
+        if let Some(v) = coding_vectors.iter().find(|v| v.len() != self.chunk_count) {
+            return Err(RLNCError::InvalidCodingVectorLength(v.len(), self.chunk_count));
+        }
+
+        let symbol_count = self.chunks.layout().symbols_per_chunk;
+        let mut results = vec![vec![F::ZERO; symbol_count]; coding_vectors.len()];
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            // Only parallelize if the total work is large enough, see `should_parallelize`.
+            let total_work = coding_vectors.len() * self.chunk_count * self.chunk_size;
+
+            if total_work >= 1024 * 512 && coding_vectors.len() > BATCH_ROWS_PER_TASK {
+                results
+                    .par_chunks_mut(BATCH_ROWS_PER_TASK)
+                    .zip(coding_vectors.par_chunks(BATCH_ROWS_PER_TASK))
+                    .for_each(|(results, vectors)| self.encode_batch_inner(vectors, results));
+            } else {
+                self.encode_batch_inner(coding_vectors, &mut results);
+            }
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        self.encode_batch_inner(coding_vectors, &mut results);
+
+        Ok(coding_vectors
+            .iter()
+            .zip(results)
+            .map(|(coding_vector, data)| RLNCPacket { coding_vector: coding_vector.clone(), data })
+            .collect())
+    }
+
+    /// Sequentially computes the coded data for the given coding vectors, blocked over symbol
+    /// columns.
+    fn encode_batch_inner(&self, coding_vectors: &[Vec<F>], results: &mut [Vec<F>]) {
+        let symbol_count = self.chunks.layout().symbols_per_chunk;
+
+        for start in (0..symbol_count).step_by(BATCH_BLOCK_SYMBOLS) {
+            let end = (start + BATCH_BLOCK_SYMBOLS).min(symbol_count);
+
+            for (i, chunk) in self.chunks.inner().iter().enumerate() {
+                let block = &chunk.symbols()[start..end];
+
+                for (result, coding_vector) in results.iter_mut().zip(coding_vectors) {
+                    let coefficient = coding_vector[i];
+                    if coefficient.is_zero_vartime() {
+                        continue;
+                    }
+
+                    for (acc, symbol) in result[start..end].iter_mut().zip(block) {
+                        *acc += *symbol * coefficient;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Generates a random coding vector using the provided random number generator.
+    fn random_coding_vector<R: Rng>(&self, rng: &mut R) -> Vec<F> {
+        (0..self.chunk_count).map(|_| random_coefficient(rng)).collect()
+    }
+}
+
+/// Generates a random coefficient from [`Field::SAFE_CAPACITY`] random bytes.
+pub fn random_coefficient<F: Field, R: Rng>(rng: &mut R) -> F {
+    let mut bytes = [0u8; 32];
+    rng.fill(&mut bytes[..F::SAFE_CAPACITY]);
+    F::from_bytes(&bytes)
 }
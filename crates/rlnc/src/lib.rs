@@ -9,6 +9,8 @@ pub mod encode;
 mod matrix;
 pub mod primitives;
 
+pub use common::RLNCError;
+
 #[cfg(test)]
 mod tests {
     use blstrs::G1Projective;
@@ -18,7 +20,17 @@ mod tests {
 
     use zc_auth::commit::PedersenCommitter;
 
-    use super::{decode::Decoder, encode::Encoder, primitives::field::Scalar};
+    use super::{
+        common::RLNCError,
+        decode::Decoder,
+        encode::Encoder,
+        primitives::{
+            ChunkLayout, Chunks, ChunksError,
+            field::{Gf256, Scalar},
+            packet::{RLNCPacket, derive_coding_vector},
+            packing::Packing,
+        },
+    };
 
     #[test]
     fn test_encode_decode_with_random_vectors() {
@@ -121,4 +133,297 @@ mod tests {
         let decoded_data = decoded.unwrap();
         assert!(decoded_data.starts_with(original_data));
     }
+
+    #[test]
+    fn test_decode_into_buffer() {
+        let original_data = rand::rng().random_iter().take(1024 * 64).collect::<Vec<_>>();
+        let chunk_count = 8;
+
+        let encoder = Encoder::<Scalar>::new(&original_data, chunk_count).unwrap();
+        let mut decoder = Decoder::<Scalar>::new(encoder.chunk_size(), chunk_count).unwrap();
+
+        assert!(decoder.decoded_len().is_err());
+
+        for _ in 0..chunk_count {
+            decoder.push(encoder.encode(rand::rng()).unwrap()).unwrap();
+        }
+
+        assert!(decoder.can_decode());
+        assert_eq!(decoder.decoded_len().unwrap(), original_data.len());
+
+        let mut small = vec![0u8; original_data.len() - 1];
+        assert!(decoder.decode_into(&mut small).is_err());
+
+        let mut out = vec![0u8; encoder.chunk_size() * chunk_count];
+        let len = decoder.decode_into(&mut out).unwrap();
+        assert_eq!(&out[..len], &original_data[..]);
+
+        #[cfg(feature = "bytes")]
+        {
+            let mut buf = bytes::BytesMut::new();
+            let len = decoder.decode_into_buf(&mut buf).unwrap();
+            assert_eq!(len, original_data.len());
+            assert_eq!(&buf[..], &original_data[..]);
+        }
+    }
+
+    #[test]
+    fn test_chunks_from_reader_matches_slice() {
+        // Cover data that ends exactly on chunk and symbol boundaries, as well as in between.
+        for len in [1, 30, 31, 62, 100, 1000, 4096] {
+            let data = rand::rng().random_iter().take(len).collect::<Vec<_>>();
+
+            for (chunk_count, packing) in
+                (1..=7).step_by(3).flat_map(|c| [(c, Packing::Bytes), (c, Packing::Bits)])
+            {
+                let layout = ChunkLayout::new::<Scalar>(len, chunk_count, packing).unwrap();
+                let from_slice = Chunks::<Scalar>::from_layout(&data, layout).unwrap();
+                let from_reader =
+                    Chunks::<Scalar>::from_reader(data.as_slice(), len, layout).unwrap();
+
+                assert_eq!(from_slice.chunk_size(), from_reader.chunk_size());
+                for (a, b) in from_slice.inner().iter().zip(from_reader.inner()) {
+                    assert_eq!(a.symbols(), b.symbols());
+                }
+
+                let encoder = Encoder::from_chunks(from_reader);
+                let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+                let decoded = loop {
+                    if let Some(decoded) =
+                        decoder.decode(encoder.encode(rand::rng()).unwrap()).unwrap()
+                    {
+                        break decoded;
+                    }
+                };
+
+                assert_eq!(decoded, data);
+            }
+        }
+
+        // Short reads are reported as errors.
+        let layout = ChunkLayout::new::<Scalar>(4, 1, Packing::Bytes).unwrap();
+        assert!(Chunks::<Scalar>::from_reader(&[1u8, 2, 3][..], 4, layout).is_err());
+    }
+
+    #[test]
+    fn test_encoder_with_packet_size() {
+        let data = rand::rng().random_iter().take(1024 * 4).collect::<Vec<_>>();
+        let mtu = 1200;
+
+        let encoder = Encoder::<Scalar>::with_packet_size(&data, mtu).unwrap();
+        let layout = encoder.layout();
+        println!("Layout: {layout:?}, overhead: {}", layout.overhead());
+
+        assert!(layout.packet_size <= mtu);
+        assert_eq!(layout.chunk_count, encoder.chunk_count());
+        assert_eq!(layout.chunk_size, encoder.chunk_size());
+
+        // One chunk less would not fit.
+        let bigger =
+            ChunkLayout::new::<Scalar>(data.len(), layout.chunk_count - 1, layout.packing).unwrap();
+        assert!(bigger.packet_size > mtu);
+
+        let packet = encoder.encode(rand::rng()).unwrap();
+        let encoded_size = (packet.coding_vector.len() + packet.data.len()) * 32;
+        assert_eq!(encoded_size, layout.packet_size);
+
+        // The coding vector alone doesn't fit.
+        assert!(matches!(
+            Encoder::<Scalar>::with_packet_size(&data, 64),
+            Err(RLNCError::ChunksError(ChunksError::PacketSizeTooSmall(64, _)))
+        ));
+    }
+
+    #[test]
+    fn test_encode_decode_bit_packing() {
+        let original_data = rand::rng().random_iter().take(1024 * 128).collect::<Vec<_>>();
+        let chunk_count = 4;
+
+        let bytes =
+            ChunkLayout::new::<Scalar>(original_data.len(), chunk_count, Packing::Bytes).unwrap();
+        let bits =
+            ChunkLayout::new::<Scalar>(original_data.len(), chunk_count, Packing::Bits).unwrap();
+        println!("Byte packing: {bytes:?}");
+        println!("Bit packing: {bits:?}");
+        assert!(bits.symbols_per_chunk < bytes.symbols_per_chunk);
+        assert!(bits.packet_size < bytes.packet_size);
+
+        let encoder =
+            Encoder::from_chunks(Chunks::<Scalar>::from_layout(&original_data, bits).unwrap());
+        let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+
+        for _ in 0..chunk_count {
+            let packet = encoder.encode(rand::rng()).unwrap();
+            assert_eq!(packet.data.len(), bits.symbols_per_chunk);
+            decoder.push(packet).unwrap();
+        }
+
+        assert_eq!(decoder.decoded_len().unwrap(), original_data.len());
+        let mut out = vec![0u8; original_data.len()];
+        decoder.decode_into(&mut out).unwrap();
+        assert_eq!(out, original_data);
+
+        // Packets with the wrong number of symbols are rejected.
+        let mut decoder = Decoder::<Scalar>::new(bytes.chunk_size, chunk_count).unwrap();
+        assert!(matches!(
+            decoder.push(encoder.encode(rand::rng()).unwrap()),
+            Err(RLNCError::InvalidSymbolCount(..))
+        ));
+    }
+
+    #[test]
+    fn test_encode_batch() {
+        let original_data = rand::rng().random_iter().take(1024 * 1024).collect::<Vec<_>>();
+        let chunk_count = 8;
+        let packet_count = 16;
+
+        let encoder = Encoder::<Scalar>::new(&original_data, chunk_count).unwrap();
+
+        let now = Instant::now();
+        let packets = encoder.encode_batch(rand::rng(), packet_count).unwrap();
+        println!("Batch encoding time: {:?}", now.elapsed());
+        assert_eq!(packets.len(), packet_count);
+
+        // The batch encoding must match encoding every packet individually.
+        let now = Instant::now();
+        for packet in &packets {
+            let expected = encoder.encode_with_vector(&packet.coding_vector).unwrap();
+            assert_eq!(packet.data, expected.data);
+        }
+        println!("Sequential encoding time: {:?}", now.elapsed());
+
+        let mut decoder = Decoder::<Scalar>::new(encoder.chunk_size(), chunk_count).unwrap();
+        for packet in packets {
+            decoder.push(packet).unwrap();
+        }
+
+        let mut decoded = vec![0u8; original_data.len()];
+        decoder.decode_into(&mut decoded).unwrap();
+        assert_eq!(decoded, original_data);
+
+        assert!(encoder.encode_batch_with_vectors(&[vec![Scalar::ONE]]).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_gf256() {
+        for a in 1..=255 {
+            let a = Gf256(a);
+            assert_eq!(a * a.invert().unwrap(), Gf256::ONE);
+            assert_eq!(a.sqrt().unwrap().square(), a);
+        }
+
+        let original_data = rand::rng().random_iter().take(1024 * 128).collect::<Vec<_>>();
+        let chunk_count = 16;
+
+        let encoder = Encoder::<Gf256>::new(&original_data, chunk_count).unwrap();
+        let layout = encoder.layout();
+        println!("GF(2^8) layout: {layout:?}");
+        assert_eq!(layout.overhead(), chunk_count);
+
+        let mut decoder = Decoder::<Gf256>::from_layout(layout).unwrap();
+
+        let now = Instant::now();
+        let mut received = 0;
+        let decoded = loop {
+            received += 1;
+            if let Some(decoded) = decoder.decode(encoder.encode(rand::rng()).unwrap()).unwrap() {
+                break decoded;
+            }
+        };
+
+        println!("Decoding time: {:?}, packets received: {received}", now.elapsed());
+        assert_eq!(decoded, original_data);
+    }
+
+    #[test]
+    fn test_packet_bytes_roundtrip() {
+        let original_data = rand::rng().random_iter().take(1024).collect::<Vec<_>>();
+
+        let encoder = Encoder::<Scalar>::new(&original_data, 4).unwrap();
+        let packet = encoder.encode(rand::rng()).unwrap();
+        let bytes = packet.to_bytes();
+        assert_eq!(bytes.len(), encoder.layout().packet_size);
+
+        let decoded = RLNCPacket::<Scalar>::from_bytes(&bytes, 4).unwrap();
+        assert_eq!(decoded.coding_vector, packet.coding_vector);
+        assert_eq!(decoded.data, packet.data);
+
+        // Truncated packets and non-canonical scalars are rejected.
+        assert!(RLNCPacket::<Scalar>::from_bytes(&bytes[1..], 4).is_err());
+        assert!(RLNCPacket::<Scalar>::from_bytes(&[0xff; 32 * 5], 4).is_err());
+
+        let encoder = Encoder::<Gf256>::new(&original_data, 4).unwrap();
+        let packet = encoder.encode(rand::rng()).unwrap();
+        let decoded = RLNCPacket::<Gf256>::from_bytes(&packet.to_bytes(), 4).unwrap();
+        assert_eq!(decoded.data, packet.data);
+    }
+
+    #[test]
+    fn test_encode_decode_derived() {
+        let original_data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let chunk_count = 8;
+        let generation_id = [3u8; 32];
+        let peers = [[1u8; 48], [2u8; 48]];
+
+        let encoder = Encoder::<Scalar>::new(&original_data, chunk_count).unwrap();
+        let mut decoder = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+
+        // The coding vector only depends on the public key, generation id and index.
+        let vector = derive_coding_vector::<Scalar>(&peers[0], &generation_id, 0, chunk_count);
+        assert_eq!(vector, derive_coding_vector(&peers[0], &generation_id, 0, chunk_count));
+        assert_ne!(vector, derive_coding_vector(&peers[1], &generation_id, 0, chunk_count));
+        assert_ne!(vector, derive_coding_vector(&peers[0], &[4u8; 32], 0, chunk_count));
+        assert_ne!(vector, derive_coding_vector(&peers[0], &generation_id, 1, chunk_count));
+
+        // Both peers send half of the packets, without coding vectors.
+        let mut decoded = None;
+        for index in 0..chunk_count as u64 / 2 {
+            for public_key in &peers {
+                let packet = encoder.encode_derived(public_key, &generation_id, index).unwrap();
+                assert_eq!(&packet.public_key, public_key);
+
+                decoded = decoder.decode_derived(&generation_id, packet).unwrap();
+            }
+        }
+
+        assert_eq!(decoded.unwrap(), original_data);
+
+        // The coding vectors of small fields are derived as well.
+        let encoder = Encoder::<Gf256>::new(&original_data, 40).unwrap();
+        let packet = encoder.encode_derived(&peers[0], &generation_id, 0).unwrap();
+        let packet = packet.into_packet(&generation_id, 40);
+        assert_eq!(packet.coding_vector.len(), 40);
+        assert_eq!(packet.data, encoder.encode_with_vector(&packet.coding_vector).unwrap().data);
+    }
+
+    #[test]
+    fn test_recode_partial() {
+        let original_data = rand::rng().random_iter().take(1024 * 16).collect::<Vec<_>>();
+        let chunk_count = 8;
+
+        let encoder = Encoder::<Scalar>::new(&original_data, chunk_count).unwrap();
+        let mut relay = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+        let mut sink = Decoder::<Scalar>::from_layout(encoder.layout()).unwrap();
+
+        assert!(relay.recode(rand::rng()).is_err());
+
+        // The relay only knows half of the subspace, so its packets can't fill the rest.
+        for _ in 0..chunk_count / 2 {
+            relay.push(encoder.encode(rand::rng()).unwrap()).unwrap();
+        }
+
+        for _ in 0..chunk_count {
+            sink.push(relay.recode(rand::rng()).unwrap()).unwrap();
+        }
+        assert_eq!(sink.rank(), chunk_count / 2);
+
+        // The rest comes from the source.
+        let decoded = loop {
+            if let Some(decoded) = sink.decode(encoder.encode(rand::rng()).unwrap()).unwrap() {
+                break decoded;
+            }
+        };
+
+        assert_eq!(decoded, original_data);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
-    common::RLNCError,
-    primitives::{field::Field, packet::RLNCPacket},
+    common::{BOUNDARY_MARKER, RLNCError},
+    primitives::{field::Field, packet::RLNCPacket, packing::Packing},
 };
 
 /// A RREF matrix of coded packets, used to store the received coded packets and perform online
@@ -30,68 +30,131 @@ impl<F: Field> Matrix<F> {
     }
 
     /// Decodes the original data from the matrix.
-    pub(crate) fn decode(&self, chunk_size: usize) -> Result<Vec<u8>, RLNCError> {
+    pub(crate) fn decode(&self, chunk_size: usize, packing: Packing) -> Result<Vec<u8>, RLNCError> {
+        let mut decoded = vec![0u8; self.decoded_len(chunk_size, packing)?];
+        self.decode_into(chunk_size, packing, &mut decoded)?;
+
+        Ok(decoded)
+    }
+
+    /// Decodes the original data from the matrix into `out`, returning the number of bytes
+    /// written. `out` must be at least [`Matrix::decoded_len`] bytes long.
+    pub(crate) fn decode_into(
+        &self,
+        chunk_size: usize,
+        packing: Packing,
+        out: &mut [u8],
+    ) -> Result<usize, RLNCError> {
+        let len = self.decoded_len(chunk_size, packing)?;
+        if out.len() < len {
+            return Err(RLNCError::BufferTooSmall(out.len(), len));
+        }
+
+        let mut offset = 0;
+        self.write_decoded(chunk_size, packing, len, |bytes| {
+            out[offset..offset + bytes.len()].copy_from_slice(bytes);
+            offset += bytes.len();
+        });
+
+        Ok(len)
+    }
+
+    /// Returns the length of the original data, i.e. the position of the boundary marker in the
+    /// decoded data. Only the trailing symbols are inspected, nothing is allocated.
+    pub(crate) fn decoded_len(
+        &self,
+        chunk_size: usize,
+        packing: Packing,
+    ) -> Result<usize, RLNCError> {
         if !self.can_decode() {
             return Err(RLNCError::NotEnoughPackets(self.rank, self.chunk_count));
         }
 
-        let symbols_per_chunk = chunk_size.div_ceil(F::SAFE_CAPACITY);
-        let mut chunk_symbols = vec![vec![F::ZERO; symbols_per_chunk]; self.chunk_count];
+        let bits = packing.symbol_bits::<F>();
+        let mut buf = [0u8; 32];
+        let buf = &mut buf[..bits.div_ceil(8)];
+
+        // The encoder places the boundary marker at the end and pads the rest with zeros, so the
+        // marker is the last non-zero byte of the decoded data.
+        for col in (0..self.chunk_count).rev() {
+            let symbols = &self.pivot_row(col).data;
+            let Some(index) = symbols.iter().rposition(|s| !s.is_zero_vartime()) else {
+                continue;
+            };
+
+            packing.unpack_symbol(&symbols[index], buf);
+            let Some(i) = buf.iter().rposition(|&b| b != 0) else {
+                return Err(RLNCError::InvalidEncoding);
+            };
+
+            // The highest set bit of the symbol must be the highest bit of the marker.
+            let top = index * bits + i * 8 + (7 - buf[i].leading_zeros() as usize);
+            let offset = top / 8;
+
+            if top % 8 != 7 ||
+                offset >= chunk_size ||
+                packing.chunk_byte(symbols, offset) != BOUNDARY_MARKER
+            {
+                return Err(RLNCError::InvalidEncoding);
+            }
 
-        // Extract packed scalars from pivot rows (they're already normalized)
-        for (col, row_idx) in self
-            .pivots
-            .iter()
-            .enumerate()
-            .filter_map(|(i, &r)| r.map(|r| (i, r)))
-            .take(self.chunk_count)
-        {
-            let row = &self.data[row_idx];
-            // Copy the packed scalars directly
-            chunk_symbols[col].copy_from_slice(&row.data);
+            return Ok(col * chunk_size + offset);
         }
 
-        // Convert packed scalars back to bytes
-        let mut decoded = Vec::with_capacity(chunk_size * self.chunk_count);
-        for chunk in chunk_symbols {
-            let chunk_bytes = chunk.iter().flat_map(|s| s.to_bytes()).collect::<Vec<_>>();
-            decoded.extend_from_slice(&chunk_bytes);
-        }
+        Err(RLNCError::InvalidEncoding)
+    }
 
-        // Find the LAST boundary marker and truncate (since encoder places it at the end)
-        let Some(boundary_pos) = decoded.iter().rposition(|&b| b == crate::common::BOUNDARY_MARKER)
-        else {
-            return Err(RLNCError::InvalidEncoding);
-        };
+    /// Feeds the first `len` bytes of the decoded data to `sink`, one symbol at a time.
+    pub(crate) fn write_decoded(
+        &self,
+        chunk_size: usize,
+        packing: Packing,
+        len: usize,
+        mut sink: impl FnMut(&[u8]),
+    ) {
+        let mut remaining = len;
+        for col in 0..self.chunk_count {
+            if remaining == 0 {
+                break;
+            }
 
-        decoded.truncate(boundary_pos);
-        Ok(decoded)
+            // Pivot rows are already normalized, so their data is the original chunk.
+            let n = remaining.min(chunk_size);
+            packing.unpack_chunk(&self.pivot_row(col).data, n, &mut sink);
+            remaining -= n;
+        }
+    }
+
+    /// Returns the row holding the pivot for the given column. Panics if the column has no pivot,
+    /// which can't happen once the matrix has full rank.
+    fn pivot_row(&self, col: usize) -> &RLNCPacket<F> {
+        &self.data[self.pivots[col].expect("full rank matrix has a pivot in every column")]
     }
 
     /// Pushes a new packet into the matrix, which will be eliminated against the existing rows.
     pub(crate) fn push_rref(&mut self, mut packet: RLNCPacket<F>) -> bool {
         self.eliminate(&mut packet);
 
-        if let Some(col) = packet.leading_coefficient() {
-            if self.pivots[col].is_none() {
-                // Normalize the packet so the leading coefficient is 1
-                packet.normalize();
+        if let Some(col) = packet.leading_coefficient() &&
+            self.pivots[col].is_none()
+        {
+            // Normalize the packet so the leading coefficient is 1
+            packet.normalize();
 
-                // Store the pivot column -> row mapping
-                self.pivots[col] = Some(self.data.len());
-                self.data.push(packet);
+            // Store the pivot column -> row mapping
+            self.pivots[col] = Some(self.data.len());
+            self.data.push(packet);
 
-                self.back_substitute(self.data.len() - 1);
-                self.rank += 1;
+            self.back_substitute(self.data.len() - 1);
+            self.rank += 1;
 
-                return self.can_decode();
-            }
+            return self.can_decode();
         }
 
         false
     }
 
-    fn eliminate(&mut self, packet: &mut RLNCPacket<F>) {
+    fn eliminate(&self, packet: &mut RLNCPacket<F>) {
         // Process pivots in column order (array index order)
         for (col, row) in self
             .pivots
@@ -129,6 +192,11 @@ impl<F: Field> Matrix<F> {
         }
     }
 
+    /// Returns the linearly independent rows received so far.
+    pub(crate) fn rows(&self) -> &[RLNCPacket<F>] {
+        &self.data
+    }
+
     #[inline]
     pub(crate) const fn rank(&self) -> usize {
         self.rank
@@ -1,6 +1,7 @@
 //! Field elements.
 pub(crate) use blstrs::Scalar;
 pub(crate) use group::ff::Field as FiniteField;
+use sha2::{Digest, Sha256};
 
 /// A field element. This trait inherits from [`ff::Field`](group::ff::Field) and adds methods
 /// for converting to and from byte slices.
@@ -10,23 +11,310 @@ pub trait Field: FiniteField {
     /// The maximum number of bytes that can be safely stored in a field element.
     const SAFE_CAPACITY: usize;
 
+    /// The number of bytes of a fully serialized field element, as sent over the wire in coded
+    /// packets (coding vector coefficients and coded symbols).
+    const ENCODED_SIZE: usize;
+
+    /// The maximum number of bits that can be safely stored in a field element. Used by
+    /// [`Packing::Bits`](super::packing::Packing::Bits). Defaults to the bits of
+    /// [`Field::SAFE_CAPACITY`] bytes.
+    const SAFE_BITS: usize = Self::SAFE_CAPACITY * 8;
+
     /// Converts a byte slice into a field element.
     fn from_bytes(bytes: &[u8]) -> Self;
 
     /// Converts a field element into a byte vector.
     fn to_bytes(&self) -> Vec<u8>;
+
+    /// Writes the safe bytes of the field element into `out`, which must be exactly
+    /// [`Field::SAFE_CAPACITY`] bytes long. Unlike [`Field::to_bytes`], this does not allocate.
+    fn write_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_bytes());
+    }
+
+    /// Writes the full canonical encoding of the field element into `out`, which must be exactly
+    /// [`Field::ENCODED_SIZE`] bytes long. This is the wire format of coded packets.
+    ///
+    /// The default writes [`Field::to_bytes`] followed by zeros, which only covers every element
+    /// if it's lossless. Fields with elements larger than [`Field::SAFE_CAPACITY`] bytes must
+    /// override this and [`Field::from_encoded`].
+    fn write_encoded(&self, out: &mut [u8]) {
+        let bytes = self.to_bytes();
+        out[..bytes.len()].copy_from_slice(&bytes);
+        out[bytes.len()..].fill(0);
+    }
+
+    /// Decodes a field element from its canonical encoding, see [`Field::write_encoded`].
+    /// Returns `None` if the bytes don't encode a valid element.
+    fn from_encoded(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::ENCODED_SIZE || bytes[Self::SAFE_CAPACITY..].iter().any(|&b| b != 0)
+        {
+            return None;
+        }
+
+        // Bytes that don't round-trip aren't the canonical encoding of an element.
+        let element = Self::from_bytes(&bytes[..Self::SAFE_CAPACITY]);
+        (element.to_bytes() == bytes[..Self::SAFE_CAPACITY]).then_some(element)
+    }
+
+    /// Converts little-endian bytes holding at most [`Field::SAFE_BITS`] bits into a field
+    /// element.
+    fn from_bits_le(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+
+    /// Writes the [`Field::SAFE_BITS`] low bits of the field element into `out` in little-endian
+    /// order. `out` must be exactly `SAFE_BITS.div_ceil(8)` bytes long.
+    fn write_bits_le(&self, out: &mut [u8]) {
+        self.write_bytes(out);
+    }
+}
+
+/// Expands the inputs into `len` field elements, by running SHA-256 in counter mode over the
+/// domain separation tag, the inputs and a little-endian `u64` block counter, and taking
+/// [`Field::SAFE_CAPACITY`] bytes per element.
+pub fn expand_field_elements<F: Field>(dst: &[u8], inputs: &[&[u8]], len: usize) -> Vec<F> {
+    let mut elements = Vec::with_capacity(len);
+
+    let per_block = 32 / F::SAFE_CAPACITY;
+    for block in 0u64.. {
+        if elements.len() == len {
+            break;
+        }
+
+        let mut hasher = Sha256::new().chain_update(dst);
+        for input in inputs {
+            hasher.update(input);
+        }
+        let digest = hasher.chain_update(block.to_le_bytes()).finalize();
+
+        for bytes in digest.chunks_exact(F::SAFE_CAPACITY).take(per_block.min(len - elements.len()))
+        {
+            elements.push(F::from_bytes(bytes));
+        }
+    }
+
+    elements
 }
 
 impl Field for Scalar {
     const SAFE_CAPACITY: usize = 31;
+    const ENCODED_SIZE: usize = 32;
+    // The modulus is slightly larger than 2^254, so any 254-bit value is a canonical scalar.
+    const SAFE_BITS: usize = 254;
 
     fn from_bytes(bytes: &[u8]) -> Self {
         let mut buf = [0u8; 32];
         buf[..bytes.len()].copy_from_slice(bytes);
-        Scalar::from_bytes_le(&buf).unwrap()
+        Self::from_bytes_le(&buf).unwrap()
     }
 
     fn to_bytes(&self) -> Vec<u8> {
         self.to_bytes_le()[..Self::SAFE_CAPACITY].to_vec()
     }
+
+    fn write_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_bytes_le()[..Self::SAFE_CAPACITY]);
+    }
+
+    fn write_encoded(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_bytes_le());
+    }
+
+    fn from_encoded(bytes: &[u8]) -> Option<Self> {
+        Self::from_bytes_le(bytes.try_into().ok()?).into()
+    }
+
+    fn write_bits_le(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_bytes_le());
+    }
+}
+
+/// The irreducible polynomial `x^8 + x^4 + x^3 + x^2 + 1` that defines [`Gf256`].
+const GF256_POLYNOMIAL: u16 = 0x11d;
+
+/// Logarithm and exponent tables for [`Gf256`], with respect to the generator `x` (i.e. 2).
+/// The exponent table is doubled, so products of two logarithms can be looked up directly.
+const GF256_TABLES: ([u8; 256], [u8; 510]) = {
+    let mut log = [0u8; 256];
+    let mut exp = [0u8; 510];
+
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        log[x as usize] = i as u8;
+
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= GF256_POLYNOMIAL;
+        }
+        i += 1;
+    }
+
+    (log, exp)
+};
+
+/// An element of the binary extension field GF(2^8), where addition is XOR and multiplication is
+/// carry-less modulo `x^8 + x^4 + x^3 + x^2 + 1`. Every byte is a valid element, so symbols hold
+/// exactly one byte and coded packets have almost no packing overhead.
+///
+/// Multiplication uses log/exp table lookups, which are **not** constant time. This is fine for
+/// coding, but the field shouldn't be used for secrets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Gf256(pub u8);
+
+impl Gf256 {
+    const fn mul_inner(self, rhs: Self) -> Self {
+        if self.0 == 0 || rhs.0 == 0 {
+            return Self(0);
+        }
+
+        let (log, exp) = &GF256_TABLES;
+        Self(exp[log[self.0 as usize] as usize + log[rhs.0 as usize] as usize])
+    }
+}
+
+impl subtle::ConditionallySelectable for Gf256 {
+    fn conditional_select(a: &Self, b: &Self, choice: subtle::Choice) -> Self {
+        Self(u8::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+impl subtle::ConstantTimeEq for Gf256 {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl std::ops::Neg for Gf256 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self
+    }
+}
+
+macro_rules! impl_gf256_op {
+    ($op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident, $inner:expr) => {
+        impl std::ops::$op for Gf256 {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                $inner(self, rhs)
+            }
+        }
+
+        impl std::ops::$op<&Self> for Gf256 {
+            type Output = Self;
+
+            fn $fn(self, rhs: &Self) -> Self {
+                $inner(self, *rhs)
+            }
+        }
+
+        impl std::ops::$op_assign for Gf256 {
+            fn $fn_assign(&mut self, rhs: Self) {
+                *self = $inner(*self, rhs);
+            }
+        }
+
+        impl std::ops::$op_assign<&Self> for Gf256 {
+            fn $fn_assign(&mut self, rhs: &Self) {
+                *self = $inner(*self, *rhs);
+            }
+        }
+    };
+}
+
+impl_gf256_op!(Add, add, AddAssign, add_assign, |a: Gf256, b: Gf256| Gf256(a.0 ^ b.0));
+impl_gf256_op!(Sub, sub, SubAssign, sub_assign, |a: Gf256, b: Gf256| Gf256(a.0 ^ b.0));
+impl_gf256_op!(Mul, mul, MulAssign, mul_assign, Gf256::mul_inner);
+
+impl std::iter::Sum for Gf256 {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self(0), |a, b| a + b)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Self> for Gf256 {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self(0), |a, b| a + b)
+    }
+}
+
+impl std::iter::Product for Gf256 {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self(1), |a, b| a * b)
+    }
+}
+
+impl<'a> std::iter::Product<&'a Self> for Gf256 {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self(1), |a, b| a * b)
+    }
+}
+
+impl FiniteField for Gf256 {
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self(1);
+
+    fn random(mut rng: impl rand_core::RngCore) -> Self {
+        Self(rng.next_u32() as u8)
+    }
+
+    fn square(&self) -> Self {
+        *self * self
+    }
+
+    fn double(&self) -> Self {
+        // Characteristic 2: x + x = 0.
+        Self(0)
+    }
+
+    fn invert(&self) -> subtle::CtOption<Self> {
+        let (log, exp) = &GF256_TABLES;
+        let inverse = Self(exp[(255 - log[self.0 as usize] as usize) % 255]);
+
+        subtle::CtOption::new(inverse, !self.is_zero())
+    }
+
+    fn sqrt_ratio(num: &Self, div: &Self) -> (subtle::Choice, Self) {
+        if div.0 == 0 {
+            return (num.is_zero(), Self(0));
+        }
+
+        // Every element of a binary field is a square, with sqrt(x) = x^(2^7).
+        let ratio = *num * div.invert().unwrap();
+        (1.into(), ratio.pow_vartime([128u64]))
+    }
+}
+
+impl Field for Gf256 {
+    const SAFE_CAPACITY: usize = 1;
+    const ENCODED_SIZE: usize = 1;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes[0])
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    fn write_bytes(&self, out: &mut [u8]) {
+        out[0] = self.0;
+    }
+
+    fn write_encoded(&self, out: &mut [u8]) {
+        out[0] = self.0;
+    }
+
+    fn from_encoded(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            &[b] => Some(Self(b)),
+            _ => None,
+        }
+    }
 }
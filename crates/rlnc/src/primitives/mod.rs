@@ -1,7 +1,11 @@
 //! RLNC primitives.
 pub mod field;
 pub mod packet;
+pub mod packing;
+use std::io::Read;
+
 use field::Field;
+use packing::Packing;
 
 use crate::common::BOUNDARY_MARKER;
 
@@ -10,7 +14,14 @@ use crate::common::BOUNDARY_MARKER;
 #[derive(Debug)]
 pub struct Chunks<F: Field> {
     inner: Vec<Chunk<F>>,
-    chunk_size: usize,
+    layout: ChunkLayout,
+}
+
+/// Returns the offset of the boundary marker within chunk `index`, if the marker falls into that
+/// chunk. The marker is placed right after the last byte of the data.
+fn marker_offset(len: usize, index: usize, chunk_size: usize) -> Option<usize> {
+    let start = index * chunk_size;
+    (start..start + chunk_size).contains(&len).then(|| len - start)
 }
 
 /// Errors that can occur when creating a new collection of chunks.
@@ -25,42 +36,102 @@ pub enum ChunksError {
     /// The chunk size is zero.
     #[error("chunk size is zero")]
     ZeroChunkSize,
+    /// The layout is inconsistent with the field.
+    #[error("invalid chunk layout")]
+    InvalidLayout,
+    /// The data doesn't fit into the layout.
+    #[error("data of {0} bytes doesn't fit into layout, can hold at most {1}")]
+    DataTooLarge(usize, usize),
+    /// No layout fits the requested packet size.
+    #[error("packet size {0} is too small, need at least {1}")]
+    PacketSizeTooSmall(usize, usize),
+    /// Reading the data failed.
+    #[error("failed to read data: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl<F: Field> Chunks<F> {
     /// Creates a new collection of chunks from a slice of bytes. The data is split into
     /// `chunk_count` equally sized chunks, and then converted into symbols (scalars) of the
-    /// field `F`. See also [`Chunk`] for more details.
+    /// field `F` using [`Packing::Bytes`]. See also [`Chunk`] for more details.
+    ///
+    /// Symbols are packed directly from the borrowed data, without copying it into an
+    /// intermediate padded buffer. This also makes it suitable for memory-mapped input.
     pub fn new(data: &[u8], chunk_count: usize) -> Result<Self, ChunksError> {
-        if data.is_empty() {
-            return Err(ChunksError::EmptyData);
-        }
+        Self::from_layout(data, ChunkLayout::new::<F>(data.len(), chunk_count, Packing::Bytes)?)
+    }
 
-        if chunk_count == 0 {
-            return Err(ChunksError::ZeroChunkCount);
-        }
+    /// Creates a new collection of chunks from a slice of bytes, choosing the chunk count such
+    /// that every coded packet (coding vector + data) fits into `packet_size` bytes. See
+    /// [`ChunkLayout::for_packet_size`] for more details.
+    pub fn with_packet_size(data: &[u8], packet_size: usize) -> Result<Self, ChunksError> {
+        let layout = ChunkLayout::for_packet_size::<F>(data.len(), packet_size, Packing::Bytes)?;
+        Self::from_layout(data, layout)
+    }
 
-        let mut data = Vec::from(data.as_ref());
-        data.push(BOUNDARY_MARKER);
+    /// Creates a new collection of chunks from a slice of bytes with the given layout, which
+    /// must have been computed for `F` and must be large enough to hold the data.
+    pub fn from_layout(data: &[u8], layout: ChunkLayout) -> Result<Self, ChunksError> {
+        layout.validate::<F>(data.len())?;
 
-        // Calculate chunk size to accommodate original data + boundary marker
-        let chunk_size = data.len().div_ceil(chunk_count);
+        let chunk_size = layout.chunk_size;
+        let chunks = (0..layout.chunk_count)
+            .map(|i| {
+                let start = (i * chunk_size).min(data.len());
+                let end = ((i + 1) * chunk_size).min(data.len());
+
+                Chunk::from_bytes(
+                    &data[start..end],
+                    &layout,
+                    marker_offset(data.len(), i, chunk_size),
+                )
+            })
+            .collect();
+
+        Ok(Self { inner: chunks, layout })
+    }
 
-        // Round up chunk size to nearest multiple of `F::SAFE_CAPACITY` for scalar packing
-        let chunk_size = chunk_size.div_ceil(F::SAFE_CAPACITY) * F::SAFE_CAPACITY;
-        let padded_len = chunk_size * chunk_count;
+    /// Creates a new collection of chunks by reading exactly `len` bytes from `reader`, with the
+    /// given layout. Only a single chunk worth of bytes is buffered at a time, so the input never
+    /// has to be fully loaded into memory.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        len: usize,
+        layout: ChunkLayout,
+    ) -> Result<Self, ChunksError> {
+        layout.validate::<F>(len)?;
 
-        // Pad the rest with zeros if needed
-        data.resize(padded_len, 0);
+        let chunk_size = layout.chunk_size;
+        let mut buf = vec![0u8; chunk_size];
+        let mut chunks = Vec::with_capacity(layout.chunk_count);
+        for i in 0..layout.chunk_count {
+            let n = len.saturating_sub(i * chunk_size).min(chunk_size);
+            reader.read_exact(&mut buf[..n])?;
 
-        let chunks = data.chunks_exact(chunk_size).map(Chunk::from_bytes).collect();
+            chunks.push(Chunk::from_bytes(&buf[..n], &layout, marker_offset(len, i, chunk_size)));
+        }
 
-        Ok(Self { inner: chunks, chunk_size })
+        Ok(Self { inner: chunks, layout })
+    }
+
+    /// Creates a new collection of chunks from a memory-mapped file.
+    #[cfg(feature = "mmap")]
+    pub fn from_file(file: &std::fs::File, chunk_count: usize) -> Result<Self, ChunksError> {
+        // SAFETY: the mapping is only read for the duration of this call. Callers must ensure the
+        // file isn't truncated concurrently, as documented by `memmap2`.
+        let mmap = unsafe { memmap2::Mmap::map(file)? };
+
+        Self::new(&mmap, chunk_count)
     }
 
     /// Returns the size of the chunks in bytes.
-    pub fn chunk_size(&self) -> usize {
-        self.chunk_size
+    pub const fn chunk_size(&self) -> usize {
+        self.layout.chunk_size
+    }
+
+    /// Returns the layout of the chunks and the resulting coded packets.
+    pub const fn layout(&self) -> ChunkLayout {
+        self.layout
     }
 
     /// Returns the inner chunks.
@@ -69,16 +140,136 @@ impl<F: Field> Chunks<F> {
     }
 
     /// Returns the number of chunks in the collection.
-    pub fn len(&self) -> usize {
+    pub const fn len(&self) -> usize {
         self.inner.len()
     }
 
     /// Returns true if the collection is empty.
-    pub fn is_empty(&self) -> bool {
+    pub const fn is_empty(&self) -> bool {
         self.inner.is_empty()
     }
 }
 
+/// Describes how data is split into chunks, and the size of the resulting coded packets for a
+/// given [`Field`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLayout {
+    /// The number of chunks (generation size).
+    pub chunk_count: usize,
+    /// The size of each chunk in bytes.
+    pub chunk_size: usize,
+    /// The number of symbols in each chunk.
+    pub symbols_per_chunk: usize,
+    /// The size of a serialized coded packet in bytes, i.e. the coding vector plus the coded
+    /// symbols.
+    pub packet_size: usize,
+    /// How the bytes of each chunk are packed into symbols.
+    pub packing: Packing,
+}
+
+impl ChunkLayout {
+    /// Returns the layout for `len` bytes of data split into `chunk_count` chunks.
+    pub const fn new<F: Field>(
+        len: usize,
+        chunk_count: usize,
+        packing: Packing,
+    ) -> Result<Self, ChunksError> {
+        if len == 0 {
+            return Err(ChunksError::EmptyData);
+        }
+
+        if chunk_count == 0 {
+            return Err(ChunksError::ZeroChunkCount);
+        }
+
+        // Calculate chunk size to accommodate original data + boundary marker
+        let chunk_size = (len + 1).div_ceil(chunk_count);
+
+        // Round up chunk size to fill the symbols it's packed into
+        let chunk_size = packing.round_chunk_size::<F>(chunk_size);
+
+        Ok(Self::from_chunk_size::<F>(chunk_count, chunk_size, packing))
+    }
+
+    /// Returns the layout with the smallest chunk count (i.e. the largest chunks) for `len`
+    /// bytes of data, such that every coded packet fits into `packet_size` bytes. This is
+    /// typically derived from the network MTU.
+    ///
+    /// Note that the coding vector grows with the chunk count, so there is a minimum packet size
+    /// for any given data length. If `packet_size` is smaller than that, this returns
+    /// [`ChunksError::PacketSizeTooSmall`].
+    pub fn for_packet_size<F: Field>(
+        len: usize,
+        packet_size: usize,
+        packing: Packing,
+    ) -> Result<Self, ChunksError> {
+        let mut min = usize::MAX;
+
+        for chunk_count in 1..=len + 1 {
+            let layout = Self::new::<F>(len, chunk_count, packing)?;
+            if layout.packet_size <= packet_size {
+                return Ok(layout);
+            }
+
+            min = min.min(layout.packet_size);
+
+            // Every packet holds at least one symbol next to its coding vector, so no larger chunk
+            // count can produce a smaller packet than the best one so far.
+            if (chunk_count + 2) * F::ENCODED_SIZE > min {
+                break;
+            }
+        }
+
+        Err(ChunksError::PacketSizeTooSmall(packet_size, min))
+    }
+
+    /// Returns the layout of `chunk_count` chunks of `chunk_size` bytes each, deriving the other
+    /// fields. The chunk size must already be rounded up to fill its symbols.
+    pub const fn from_chunk_size<F: Field>(
+        chunk_count: usize,
+        chunk_size: usize,
+        packing: Packing,
+    ) -> Self {
+        let symbols_per_chunk = packing.symbols_per_chunk::<F>(chunk_size);
+        let packet_size = (chunk_count + symbols_per_chunk) * F::ENCODED_SIZE;
+
+        Self { chunk_count, chunk_size, symbols_per_chunk, packet_size, packing }
+    }
+
+    /// Checks that the layout is consistent for `F` and can hold `len` bytes of data plus the
+    /// boundary marker.
+    fn validate<F: Field>(&self, len: usize) -> Result<(), ChunksError> {
+        if len == 0 {
+            return Err(ChunksError::EmptyData);
+        }
+
+        if self.chunk_count == 0 {
+            return Err(ChunksError::ZeroChunkCount);
+        }
+
+        if self.chunk_size == 0 {
+            return Err(ChunksError::ZeroChunkSize);
+        }
+
+        if *self != Self::from_chunk_size::<F>(self.chunk_count, self.chunk_size, self.packing) {
+            return Err(ChunksError::InvalidLayout);
+        }
+
+        let capacity = self.chunk_count * self.chunk_size;
+        if capacity <= len {
+            return Err(ChunksError::DataTooLarge(len, capacity - 1));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the per-packet overhead in bytes: the size of the coding vector plus the expansion
+    /// of packing the chunk's bytes into [`Field::ENCODED_SIZE`] byte symbols.
+    pub const fn overhead(&self) -> usize {
+        self.packet_size - self.chunk_size
+    }
+}
+
 /// A chunk of data.
 #[derive(Debug, Clone)]
 pub struct Chunk<F: Field> {
@@ -88,15 +279,47 @@ pub struct Chunk<F: Field> {
 }
 
 impl<F: Field> Chunk<F> {
-    /// Creates a new chunk from a slice of bytes, and converts it into a vector of scalars
-    /// (symbols used for encoding).
-    pub(crate) fn from_bytes(bytes: &[u8]) -> Self {
-        let size = bytes.len();
-        Self { symbols: bytes.chunks(F::SAFE_CAPACITY).map(|c| F::from_bytes(c)).collect(), size }
+    /// Creates a new chunk from a slice of bytes, and converts it into a vector of scalars (symbols
+    /// used for encoding) according to the layout. `bytes` may be shorter than the chunk size, in
+    /// which case the rest of the chunk is zero-padded. If `marker` is set, the boundary marker is
+    /// written at that offset.
+    pub(crate) fn from_bytes(bytes: &[u8], layout: &ChunkLayout, marker: Option<usize>) -> Self {
+        let symbols = match layout.packing {
+            Packing::Bytes => (0..layout.symbols_per_chunk)
+                .map(|i| {
+                    let start = i * F::SAFE_CAPACITY;
+                    let mut buf = [0u8; 32];
+
+                    if start < bytes.len() {
+                        let end = (start + F::SAFE_CAPACITY).min(bytes.len());
+                        buf[..end - start].copy_from_slice(&bytes[start..end]);
+                    }
+
+                    if let Some(offset) = marker &&
+                        (start..start + F::SAFE_CAPACITY).contains(&offset)
+                    {
+                        buf[offset - start] = BOUNDARY_MARKER;
+                    }
+
+                    F::from_bytes(&buf[..F::SAFE_CAPACITY])
+                })
+                .collect(),
+            Packing::Bits => {
+                let byte_at = |i: usize| match bytes.get(i) {
+                    Some(&b) => b,
+                    None if marker == Some(i) => BOUNDARY_MARKER,
+                    None => 0,
+                };
+
+                (0..layout.symbols_per_chunk).map(|i| Packing::pack_bits(i, byte_at)).collect()
+            }
+        };
+
+        Self { symbols, size: layout.chunk_size }
     }
 
     /// Returns the symbols of the chunk.
-    pub(crate) fn symbols(&self) -> &[F] {
+    pub fn symbols(&self) -> &[F] {
         &self.symbols
     }
 }
@@ -1,5 +1,9 @@
 //! RLNC coded packet.
-use super::field::Field;
+use super::field::{Field, expand_field_elements};
+use crate::common::RLNCError;
+
+/// The domain separation tag for deriving coding vectors, see [`derive_coding_vector`].
+const DERIVE_DST: &[u8] = b"RLNC_DERIVED_CODING_VECTOR";
 
 /// A coded packet.
 #[derive(Debug, Clone)]
@@ -11,6 +15,40 @@ pub struct RLNCPacket<F: Field> {
 }
 
 impl<F: Field> RLNCPacket<F> {
+    /// Serializes the packet: the coding vector followed by the coded symbols, each encoded with
+    /// [`Field::write_encoded`]. The length is the layout's
+    /// [`packet_size`](super::ChunkLayout::packet_size).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; (self.coding_vector.len() + self.data.len()) * F::ENCODED_SIZE];
+
+        for (out, element) in
+            bytes.chunks_exact_mut(F::ENCODED_SIZE).zip(self.coding_vector.iter().chain(&self.data))
+        {
+            element.write_encoded(out);
+        }
+
+        bytes
+    }
+
+    /// Deserializes a packet with a coding vector of `chunk_count` coefficients, see
+    /// [`RLNCPacket::to_bytes`].
+    pub fn from_bytes(bytes: &[u8], chunk_count: usize) -> Result<Self, RLNCError> {
+        if !bytes.len().is_multiple_of(F::ENCODED_SIZE) ||
+            bytes.len() / F::ENCODED_SIZE <= chunk_count
+        {
+            return Err(RLNCError::InvalidPacketEncoding);
+        }
+
+        let mut elements = bytes
+            .chunks_exact(F::ENCODED_SIZE)
+            .map(F::from_encoded)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(RLNCError::InvalidPacketEncoding)?;
+
+        let data = elements.split_off(chunk_count);
+        Ok(Self { coding_vector: elements, data })
+    }
+
     /// Returns the number of non-zero coefficients in the coding vector.
     pub fn degree(&self) -> usize {
         self.coding_vector.iter().filter(|&c| !c.is_zero_vartime()).count()
@@ -27,11 +65,11 @@ impl<F: Field> RLNCPacket<F> {
             let inv = self.coding_vector[col].invert().unwrap();
 
             for i in 0..self.coding_vector.len() {
-                self.coding_vector[i] = self.coding_vector[i] * inv;
+                self.coding_vector[i] *= inv;
             }
 
             for i in 0..self.data.len() {
-                self.data[i] = self.data[i] * inv;
+                self.data[i] *= inv;
             }
         }
     }
@@ -47,3 +85,61 @@ impl<F: Field> RLNCPacket<F> {
         }
     }
 }
+
+/// A coded packet whose coding vector is derived from the sender's public key, the generation id
+/// and the packet index with [`derive_coding_vector`], so it doesn't need to be sent.
+///
+/// The public key attributes the packet to the peer that produced it, but it isn't authenticated
+/// by itself: anyone can derive the coding vectors of any key. Relays can't recode derived
+/// packets, because a combination of them doesn't have a derived coding vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedPacket<F: Field> {
+    /// The public key of the peer that produced the packet.
+    pub public_key: Vec<u8>,
+    /// The index of the packet among the packets of the peer for this generation.
+    pub index: u64,
+    /// The coded symbols.
+    pub data: Vec<F>,
+}
+
+impl<F: Field> DerivedPacket<F> {
+    /// Derives the coding vector of the packet for the given generation id and chunk count.
+    pub fn coding_vector(&self, generation_id: &[u8; 32], chunk_count: usize) -> Vec<F> {
+        derive_coding_vector(&self.public_key, generation_id, self.index, chunk_count)
+    }
+
+    /// Converts the packet into a regular packet with an explicit coding vector, e.g. to recode
+    /// it.
+    pub fn into_packet(self, generation_id: &[u8; 32], chunk_count: usize) -> RLNCPacket<F> {
+        RLNCPacket {
+            coding_vector: self.coding_vector(generation_id, chunk_count),
+            data: self.data,
+        }
+    }
+}
+
+/// Derives a coding vector of `chunk_count` coefficients from the sender's public key, the
+/// generation id and the packet index, by running SHA-256 in counter mode and taking
+/// [`Field::SAFE_CAPACITY`] bytes per coefficient.
+///
+/// The coefficients are as uniform as the random coding vectors of
+/// [`Encoder::encode`](crate::encode::Encoder::encode), so derived packets are linearly
+/// independent with the same probability, as long as every (public key, index) pair is only used
+/// once per generation.
+pub fn derive_coding_vector<F: Field>(
+    public_key: &[u8],
+    generation_id: &[u8; 32],
+    index: u64,
+    chunk_count: usize,
+) -> Vec<F> {
+    expand_field_elements(
+        DERIVE_DST,
+        &[
+            &(public_key.len() as u64).to_le_bytes(),
+            public_key,
+            generation_id,
+            &index.to_le_bytes(),
+        ],
+        chunk_count,
+    )
+}
@@ -0,0 +1,144 @@
+//! Packing of bytes into field symbols.
+use super::field::Field;
+
+/// The maximum number of bytes a symbol can occupy when packed, see [`Field::SAFE_BITS`].
+const MAX_SYMBOL_BYTES: usize = 32;
+
+/// Determines how the bytes of a chunk are packed into symbols of a [`Field`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Packing {
+    /// Packs [`Field::SAFE_CAPACITY`] whole bytes into every symbol. For BLS12-381 scalars, this
+    /// stores 31 bytes in each 32 byte symbol.
+    #[default]
+    Bytes,
+    /// Packs [`Field::SAFE_BITS`] bits into every symbol, with bytes spanning symbol boundaries.
+    /// For BLS12-381 scalars, this stores 254 bits in each symbol, which shrinks coded payloads
+    /// by ~2.4% compared to [`Packing::Bytes`] at the cost of some bit shuffling.
+    Bits,
+}
+
+impl Packing {
+    /// Returns the number of bits of data stored in each symbol.
+    pub const fn symbol_bits<F: Field>(self) -> usize {
+        match self {
+            Self::Bytes => F::SAFE_CAPACITY * 8,
+            Self::Bits => F::SAFE_BITS,
+        }
+    }
+
+    /// Returns the number of symbols needed to hold `chunk_size` bytes.
+    pub const fn symbols_per_chunk<F: Field>(self, chunk_size: usize) -> usize {
+        (chunk_size * 8).div_ceil(self.symbol_bits::<F>())
+    }
+
+    /// Rounds `chunk_size` up to the number of bytes that fit into the symbols needed to hold it.
+    pub const fn round_chunk_size<F: Field>(self, chunk_size: usize) -> usize {
+        self.symbols_per_chunk::<F>(chunk_size) * self.symbol_bits::<F>() / 8
+    }
+
+    /// Packs the [`Packing::Bits`] symbol at `index` of a chunk, reading the chunk's bytes with
+    /// `byte_at`.
+    pub(crate) fn pack_bits<F: Field>(index: usize, byte_at: impl Fn(usize) -> u8) -> F {
+        let bits = F::SAFE_BITS;
+        let len = bits.div_ceil(8);
+
+        let start = index * bits;
+        let (first, shift) = (start / 8, start % 8);
+
+        let mut buf = [0u8; MAX_SYMBOL_BYTES];
+        for (i, b) in buf[..len].iter_mut().enumerate() {
+            *b = byte_at(first + i) >> shift;
+            if shift > 0 {
+                *b |= byte_at(first + i + 1) << (8 - shift);
+            }
+        }
+
+        // Clear the bits that belong to the next symbol.
+        if bits % 8 != 0 {
+            buf[len - 1] &= (1 << (bits % 8)) - 1;
+        }
+
+        F::from_bits_le(&buf[..len])
+    }
+
+    /// Writes the data bits of `symbol` into `out`, which must be `symbol_bits().div_ceil(8)`
+    /// bytes long.
+    pub(crate) fn unpack_symbol<F: Field>(self, symbol: &F, out: &mut [u8]) {
+        match self {
+            Self::Bytes => symbol.write_bytes(out),
+            Self::Bits => symbol.write_bits_le(out),
+        }
+    }
+
+    /// Returns the byte at `offset` of a chunk, given its symbols.
+    pub(crate) fn chunk_byte<F: Field>(self, symbols: &[F], offset: usize) -> u8 {
+        let bits = self.symbol_bits::<F>();
+        let mut buf = [0u8; MAX_SYMBOL_BYTES];
+        let buf = &mut buf[..bits.div_ceil(8)];
+
+        let mut current = None;
+        let mut byte = 0;
+        for i in 0..8 {
+            let bit = offset * 8 + i;
+            let index = bit / bits;
+
+            if current != Some(index) {
+                self.unpack_symbol(&symbols[index], buf);
+                current = Some(index);
+            }
+
+            let bit = bit % bits;
+            byte |= ((buf[bit / 8] >> (bit % 8)) & 1) << i;
+        }
+
+        byte
+    }
+
+    /// Unpacks the first `len` bytes of a chunk from its symbols, feeding them to `sink` one
+    /// symbol at a time.
+    pub(crate) fn unpack_chunk<F: Field>(
+        self,
+        symbols: &[F],
+        len: usize,
+        mut sink: impl FnMut(&[u8]),
+    ) {
+        let bits = self.symbol_bits::<F>();
+        let symbol_len = bits.div_ceil(8);
+
+        let mut buf = [0u8; MAX_SYMBOL_BYTES];
+        let mut out = [0u8; MAX_SYMBOL_BYTES + 1];
+
+        // Bits left over from the previous symbol that don't make up a full byte yet.
+        let (mut carry, mut carry_bits) = (0u8, 0);
+        let mut remaining = len;
+
+        for symbol in symbols {
+            if remaining == 0 {
+                break;
+            }
+
+            self.unpack_symbol(symbol, &mut buf[..symbol_len]);
+
+            // Prepend the carry to the symbol's bits.
+            let mut prev = 0u8;
+            for (o, &b) in out.iter_mut().zip(&buf[..symbol_len]) {
+                *o = if carry_bits == 0 {
+                    b
+                } else {
+                    (b << carry_bits) | (prev >> (8 - carry_bits))
+                };
+                prev = b;
+            }
+            out[0] |= carry;
+            out[symbol_len] = if carry_bits == 0 { 0 } else { prev >> (8 - carry_bits) };
+
+            let total = carry_bits + bits;
+            let n = (total / 8).min(remaining);
+            sink(&out[..n]);
+            remaining -= n;
+
+            carry_bits = total % 8;
+            carry = out[total / 8] & ((1 << carry_bits) - 1);
+        }
+    }
+}
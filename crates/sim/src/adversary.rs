@@ -0,0 +1,122 @@
+//! Pollution attacks and packet verification.
+use std::fmt;
+
+use blstrs::Scalar;
+use rand::Rng;
+use zc_auth::verify::Verifier;
+use zc_rlnc::{
+    decode::Decoder,
+    encode::random_coefficient,
+    primitives::{ChunkLayout, field::Field, packet::RLNCPacket},
+};
+
+/// How an attacker pollutes the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attack {
+    /// Injects packets with a random coding vector and random data.
+    Random,
+    /// Recodes the genuine packets it received and tampers with one symbol of the data, so the
+    /// coding vector is plausible. Falls back to [`Attack::Random`] before it received anything.
+    Crafted,
+}
+
+impl Attack {
+    /// Creates a polluted packet. `decoder` holds the genuine packets the attacker received.
+    pub(crate) fn packet<F: Field, R: Rng>(
+        self,
+        layout: ChunkLayout,
+        decoder: Option<&Decoder<F>>,
+        rng: &mut R,
+    ) -> RLNCPacket<F> {
+        if self == Self::Crafted &&
+            let Some(Ok(mut packet)) = decoder.map(|d| d.recode(&mut *rng))
+        {
+            let index = rng.random_range(0..packet.data.len());
+            packet.data[index] += loop {
+                let delta = random_coefficient::<F, _>(rng);
+                if !delta.is_zero_vartime() {
+                    break delta;
+                }
+            };
+            return packet;
+        }
+
+        RLNCPacket {
+            coding_vector: (0..layout.chunk_count).map(|_| random_coefficient(rng)).collect(),
+            data: (0..layout.symbols_per_chunk).map(|_| random_coefficient(rng)).collect(),
+        }
+    }
+}
+
+/// Verifies packets before honest nodes accept them, e.g. against Pedersen commitments.
+pub trait PacketVerifier<F: Field>: fmt::Debug {
+    /// Returns true if the packet is a valid combination of the original chunks.
+    fn verify(&self, packet: &RLNCPacket<F>) -> bool;
+}
+
+impl PacketVerifier<Scalar> for Verifier {
+    fn verify(&self, packet: &RLNCPacket<Scalar>) -> bool {
+        Self::verify(self, packet).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::{SeedableRng, rngs::StdRng};
+    use zc_auth::commit::{ChunkCommitments, PedersenCommitter};
+    use zc_rlnc::{encode::Encoder, primitives::field::Gf256};
+
+    use super::*;
+    use crate::{LinkConfig, Role, SimConfig, Simulation, Topology};
+
+    fn simulation<F: Field>(attack: Attack) -> Simulation<F> {
+        let topology = Topology::random_regular(30, 4, StdRng::seed_from_u64(3)).unwrap();
+        let config = SimConfig {
+            data_size: 8 * 1024,
+            chunk_count: 8,
+            link: LinkConfig::new(Duration::from_millis(20), 1_250_000, 0.01),
+            ..Default::default()
+        };
+
+        let mut simulation = Simulation::new(topology, config);
+        for attacker in [7, 19] {
+            simulation.set_role(attacker, Role::Attacker(attack));
+        }
+
+        simulation
+    }
+
+    #[test]
+    fn test_pollution_without_verification() {
+        for attack in [Attack::Random, Attack::Crafted] {
+            let report = simulation::<Gf256>(attack).run().unwrap();
+            println!("{attack:?}:\n{report}");
+
+            assert!(report.polluted_fraction() > 0.5);
+            assert!(report.failed_fraction() > 0.5);
+            assert_eq!(report.total_verify_time().1, 0);
+        }
+    }
+
+    #[test]
+    fn test_pollution_with_pedersen() {
+        for attack in [Attack::Random, Attack::Crafted] {
+            let mut simulation = simulation::<Scalar>(attack);
+
+            let chunks = Encoder::<Scalar>::prepare(simulation.data(), 8).unwrap();
+            let committer = PedersenCommitter::new([0u8; 32], chunks.layout().symbols_per_chunk);
+            let commitments = ChunkCommitments::new(&committer, &chunks).unwrap();
+            simulation.set_verifier(Verifier::new(committer, commitments));
+
+            let report = simulation.run().unwrap();
+            println!("{attack:?}:\n{report}");
+
+            assert_eq!(report.polluted_fraction(), 0.0);
+            assert_eq!(report.failed_fraction(), 0.0);
+            assert_eq!(report.decoded_fraction(), 1.0);
+            assert!(report.total_rejected() > 0);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! The discrete-event simulation of the [`Gossip`] protocol.
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use zc_gossip::{Event as GossipEvent, Gossip, GossipConfig, Message, Transmit};
+use zc_rlnc::primitives::field::Field;
+
+use crate::{
+    link::LinkConfig,
+    queue::EventQueue,
+    report::{NodeReport, Report},
+    simulation::{Role, SimConfig, SimError},
+    topology::Topology,
+};
+
+/// The generation id of the simulated broadcast.
+const GENERATION: [u8; 32] = [0; 32];
+
+/// A discrete-event simulation of broadcasting random data over a [`Topology`] with the
+/// [`Gossip`] protocol, where the peers of every node are its neighbors. Node 0 is the source.
+///
+/// Of the [`SimConfig`], only the data size, link, maximum time and seed are used. The coding
+/// parameters are taken from the [`GossipConfig`].
+#[derive(Debug)]
+pub struct GossipSimulation<F: Field> {
+    topology: Topology,
+    config: SimConfig,
+    gossip: GossipConfig,
+    links: HashMap<(usize, usize), LinkConfig>,
+    _field: PhantomData<F>,
+}
+
+impl<F: Field> GossipSimulation<F> {
+    /// Creates a new simulation.
+    pub fn new(topology: Topology, config: SimConfig, gossip: GossipConfig) -> Self {
+        Self { topology, config, gossip, links: HashMap::new(), _field: PhantomData }
+    }
+
+    /// Overrides the properties of the directed link from `from` to `to`.
+    pub fn set_link(&mut self, from: usize, to: usize, link: LinkConfig) {
+        self.links.insert((from, to), link);
+    }
+
+    /// Runs the simulation until no messages are in flight and no timeouts are pending, or the
+    /// maximum time is reached.
+    pub fn run(&self) -> Result<Report, SimError> {
+        if self.topology.is_empty() {
+            return Err(SimError::NoSource);
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let data = (&mut rng).random_iter().take(self.config.data_size).collect::<Vec<u8>>();
+
+        let nodes = (0..self.topology.len())
+            .map(|node| {
+                let peers = self.topology.neighbors(node).to_vec();
+                let role = if node == 0 { Role::Source } else { Role::Relay };
+
+                GossipNode {
+                    gossip: Gossip::new(self.gossip, peers, &mut rng),
+                    timer: None,
+                    report: NodeReport { role, ..Default::default() },
+                }
+            })
+            .collect();
+
+        let epoch = Instant::now();
+        let mut run = Run::<F> {
+            data,
+            epoch,
+            nodes,
+            queue: EventQueue::new(&self.links, self.config.link)?,
+            now: Duration::ZERO,
+            rng,
+        };
+
+        run.nodes[0].gossip.broadcast(GENERATION, &run.data, epoch)?;
+        run.flush(0);
+
+        while let Some((time, event)) = run.queue.pop() {
+            if time > self.config.max_time {
+                break;
+            }
+
+            run.now = time;
+            run.handle(event)?;
+        }
+
+        Ok(Report {
+            nodes: run.nodes.into_iter().map(|n| n.report).collect(),
+            data_size: self.config.data_size,
+            duration: run.now,
+        })
+    }
+}
+
+/// The state of a node during a simulation.
+#[derive(Debug)]
+struct GossipNode<F: Field> {
+    gossip: Gossip<F, usize>,
+    /// The time of the scheduled timeout, if any.
+    timer: Option<Duration>,
+    report: NodeReport,
+}
+
+/// What happens at a node.
+#[derive(Debug)]
+enum Action<F: Field> {
+    /// A message arrives from a peer.
+    Deliver { from: usize, message: Message<F> },
+    /// A timeout fires.
+    Timeout,
+}
+
+/// An action scheduled at a node.
+#[derive(Debug)]
+struct Event<F: Field> {
+    node: usize,
+    action: Action<F>,
+}
+
+/// The state of a running simulation.
+struct Run<'a, F: Field> {
+    data: Vec<u8>,
+    /// The instant that simulated time zero maps to.
+    epoch: Instant,
+    nodes: Vec<GossipNode<F>>,
+    queue: EventQueue<'a, Event<F>>,
+    now: Duration,
+    rng: StdRng,
+}
+
+impl<F: Field> Run<'_, F> {
+    /// Handles an event, and sends the resulting messages.
+    fn handle(&mut self, event: Event<F>) -> Result<(), SimError> {
+        let now = self.epoch + self.now;
+        let node = &mut self.nodes[event.node];
+
+        match event.action {
+            Action::Deliver { from, message } => {
+                node.report.packets_received += 1;
+                node.report.bytes_received += message.encoded_len();
+
+                let rank = node.gossip.rank(&GENERATION);
+                node.gossip.handle(from, message, now)?;
+                if node.gossip.rank(&GENERATION) > rank {
+                    node.report.innovative += 1;
+                }
+            }
+            // Stale timers were rescheduled.
+            Action::Timeout if node.timer != Some(self.now) => return Ok(()),
+            Action::Timeout => {
+                node.timer = None;
+                node.gossip.handle_timeout(now);
+            }
+        }
+
+        while let Some(event) = node.gossip.poll_event() {
+            if let GossipEvent::Decoded { data, .. } = event {
+                if data == self.data {
+                    node.report.decoded_at = Some(self.now);
+                } else {
+                    node.report.decode_failed = true;
+                }
+            }
+        }
+
+        self.flush(event.node);
+        Ok(())
+    }
+
+    /// Sends the messages of a node, and schedules its next timeout.
+    fn flush(&mut self, node: usize) {
+        while let Some(Transmit { to, message }) = self.nodes[node].gossip.poll_transmit() {
+            self.send(node, to, message);
+        }
+
+        let state = &mut self.nodes[node];
+        let timer = state.gossip.poll_timeout().map(|t| (t - self.epoch).max(self.now));
+        if let Some(time) = timer &&
+            state.timer != timer
+        {
+            state.timer = timer;
+            self.queue.schedule(time, Event { node, action: Action::Timeout });
+        }
+    }
+
+    /// Queues a message on the link from `from` to `to`.
+    fn send(&mut self, from: usize, to: usize, message: Message<F>) {
+        let size = message.encoded_len();
+        let report = &mut self.nodes[from].report;
+        report.packets_sent += 1;
+        report.bytes_sent += size;
+
+        let event = Event { node: to, action: Action::Deliver { from, message } };
+        self.queue.send(from, to, self.now, size, &mut self.rng, event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zc_rlnc::primitives::field::Gf256;
+
+    use super::*;
+
+    #[test]
+    fn test_simulate_gossip() {
+        let topology = Topology::random_regular(50, 6, StdRng::seed_from_u64(1)).unwrap();
+        let config = SimConfig {
+            link: LinkConfig::new(Duration::from_millis(20), 1_250_000, 0.05),
+            ..Default::default()
+        };
+
+        let simulation = GossipSimulation::<Gf256>::new(topology, config, GossipConfig::default());
+        let report = simulation.run().unwrap();
+        println!("{report}");
+
+        assert_eq!(report.decoded_fraction(), 1.0);
+        assert_eq!(report.failed_fraction(), 0.0);
+        assert_eq!(simulation.run().unwrap(), report);
+    }
+}
@@ -0,0 +1,77 @@
+//! Network links with latency, bandwidth and loss.
+use std::time::Duration;
+
+use rand::Rng;
+
+/// The properties of a directed link between two nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkConfig {
+    /// The propagation delay.
+    pub latency: Duration,
+    /// The maximum additional delay, sampled uniformly for every packet.
+    pub jitter: Duration,
+    /// The bandwidth in bytes per second. Packets are serialized onto the link one after the
+    /// other, so a busy link queues them.
+    pub bandwidth: u64,
+    /// The probability that a packet is lost.
+    pub loss: f64,
+}
+
+impl Default for LinkConfig {
+    /// A 100Mbit/s link with 50ms latency and no loss.
+    fn default() -> Self {
+        Self::new(Duration::from_millis(50), 12_500_000, 0.0)
+    }
+}
+
+impl LinkConfig {
+    /// Creates a link without jitter.
+    pub const fn new(latency: Duration, bandwidth: u64, loss: f64) -> Self {
+        Self { latency, jitter: Duration::ZERO, bandwidth, loss }
+    }
+
+    /// Returns true if the bandwidth is positive and the loss is a probability. Simulations
+    /// reject links that aren't valid.
+    pub fn is_valid(&self) -> bool {
+        self.bandwidth > 0 && (0.0..=1.0).contains(&self.loss)
+    }
+
+    /// Sets the jitter of the link.
+    pub const fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+}
+
+/// The state of a directed link during a simulation.
+#[derive(Debug, Clone)]
+pub(crate) struct Link {
+    pub(crate) config: LinkConfig,
+    /// The time at which the link finishes sending the last queued packet.
+    busy_until: Duration,
+}
+
+impl Link {
+    pub(crate) const fn new(config: LinkConfig) -> Self {
+        Self { config, busy_until: Duration::ZERO }
+    }
+
+    /// Sends a packet of `size` bytes at time `now`, and returns its arrival time, or `None` if
+    /// it's lost. Lost packets still take up bandwidth.
+    pub(crate) fn transmit<R: Rng>(
+        &mut self,
+        now: Duration,
+        size: usize,
+        rng: &mut R,
+    ) -> Option<Duration> {
+        let transmission = Duration::from_secs_f64(size as f64 / self.config.bandwidth as f64);
+        self.busy_until = self.busy_until.max(now) + transmission;
+
+        if rng.random_bool(self.config.loss) {
+            return None;
+        }
+
+        let jitter = self.config.jitter.mul_f64(rng.random());
+        Some(self.busy_until + self.config.latency + jitter)
+    }
+}
@@ -0,0 +1,104 @@
+//! The event queue shared by the simulators.
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    time::Duration,
+};
+
+use rand::Rng;
+
+use crate::{
+    link::{Link, LinkConfig},
+    simulation::SimError,
+};
+
+/// An event scheduled at a point in simulated time.
+#[derive(Debug)]
+struct Scheduled<T> {
+    time: Duration,
+    /// Breaks ties between events at the same time in the order they were scheduled.
+    seq: u64,
+    event: T,
+}
+
+impl<T> PartialEq for Scheduled<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.time, self.seq) == (other.time, other.seq)
+    }
+}
+
+impl<T> Eq for Scheduled<T> {}
+
+impl<T> PartialOrd for Scheduled<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Scheduled<T> {
+    /// Reversed, so that the [`BinaryHeap`] pops the earliest event first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.time, other.seq).cmp(&(self.time, self.seq))
+    }
+}
+
+/// The pending events of a simulation, and the state of the links that messages are queued on.
+#[derive(Debug)]
+pub(crate) struct EventQueue<'a, T> {
+    /// The links whose properties were overridden.
+    configs: &'a HashMap<(usize, usize), LinkConfig>,
+    /// The properties of all other links.
+    default: LinkConfig,
+    links: HashMap<(usize, usize), Link>,
+    events: BinaryHeap<Scheduled<T>>,
+    seq: u64,
+}
+
+impl<'a, T> EventQueue<'a, T> {
+    /// Creates an empty queue, or returns an error if any of the links isn't valid.
+    pub(crate) fn new(
+        configs: &'a HashMap<(usize, usize), LinkConfig>,
+        default: LinkConfig,
+    ) -> Result<Self, SimError> {
+        if let Some(&link) =
+            std::iter::once(&default).chain(configs.values()).find(|l| !l.is_valid())
+        {
+            return Err(SimError::InvalidLink(link));
+        }
+
+        Ok(Self { configs, default, links: HashMap::new(), events: BinaryHeap::new(), seq: 0 })
+    }
+
+    /// Schedules an event at `time`.
+    pub(crate) fn schedule(&mut self, time: Duration, event: T) {
+        self.seq += 1;
+        self.events.push(Scheduled { time, seq: self.seq, event });
+    }
+
+    /// Sends a message of `size` bytes on the link from `from` to `to` at time `now`, and
+    /// schedules `event` at its arrival unless it's lost.
+    pub(crate) fn send<R: Rng>(
+        &mut self,
+        from: usize,
+        to: usize,
+        now: Duration,
+        size: usize,
+        rng: &mut R,
+        event: T,
+    ) {
+        let (configs, default) = (self.configs, self.default);
+        let link = self
+            .links
+            .entry((from, to))
+            .or_insert_with(|| Link::new(configs.get(&(from, to)).copied().unwrap_or(default)));
+
+        if let Some(time) = link.transmit(now, size, rng) {
+            self.schedule(time, event);
+        }
+    }
+
+    /// Removes the earliest event, and returns it with its time.
+    pub(crate) fn pop(&mut self) -> Option<(Duration, T)> {
+        self.events.pop().map(|scheduled| (scheduled.time, scheduled.event))
+    }
+}
@@ -0,0 +1,186 @@
+//! Simulation results.
+use std::{fmt, time::Duration};
+
+use crate::simulation::Role;
+
+/// Summary statistics over a set of samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    /// The samples, sorted in ascending order.
+    samples: Vec<f64>,
+}
+
+impl Distribution {
+    /// Creates a distribution from the given samples.
+    pub fn new(mut samples: Vec<f64>) -> Self {
+        samples.sort_by(f64::total_cmp);
+        Self { samples }
+    }
+
+    /// Returns the number of samples.
+    pub const fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns true if there are no samples.
+    pub const fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the smallest sample.
+    pub fn min(&self) -> Option<f64> {
+        self.samples.first().copied()
+    }
+
+    /// Returns the largest sample.
+    pub fn max(&self) -> Option<f64> {
+        self.samples.last().copied()
+    }
+
+    /// Returns the mean of the samples.
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+
+    /// Returns the `p`-th percentile (`0.0..=1.0`) with the nearest-rank method.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        let rank = (p.clamp(0.0, 1.0) * self.samples.len() as f64).ceil() as usize;
+        self.samples.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min(), self.percentile(0.5), self.percentile(0.9), self.max(), self.mean()) {
+            (Some(min), Some(p50), Some(p90), Some(max), Some(mean)) => write!(
+                f,
+                "min {min:.3}, p50 {p50:.3}, p90 {p90:.3}, max {max:.3}, mean {mean:.3} (n = {})",
+                self.len()
+            ),
+            _ => write!(f, "no samples"),
+        }
+    }
+}
+
+/// The results of a single node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeReport {
+    /// The role of the node.
+    pub role: Role,
+    /// The time at which the node decoded the data, if it did.
+    pub decoded_at: Option<Duration>,
+    /// The number of packets sent.
+    pub packets_sent: usize,
+    /// The number of bytes sent.
+    pub bytes_sent: usize,
+    /// The number of packets received.
+    pub packets_received: usize,
+    /// The number of bytes received.
+    pub bytes_received: usize,
+    /// The number of received packets that increased the rank of the decoder.
+    pub innovative: usize,
+    /// The time at which the node first accepted a polluted packet, if it did.
+    pub polluted_at: Option<Duration>,
+    /// Whether the node reached full rank but decoded the wrong data.
+    pub decode_failed: bool,
+    /// The number of packets the node verified.
+    pub verified: usize,
+    /// The number of packets the node rejected because verification failed.
+    pub rejected: usize,
+    /// The wall-clock time the node spent verifying packets.
+    pub verify_time: Duration,
+}
+
+/// The results of a simulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The results of every node.
+    pub nodes: Vec<NodeReport>,
+    /// The size of the broadcast data in bytes.
+    pub data_size: usize,
+    /// The time of the last event.
+    pub duration: Duration,
+}
+
+impl Report {
+    /// Returns the nodes that should decode, i.e. relays and sinks.
+    fn receivers(&self) -> impl Iterator<Item = &NodeReport> {
+        self.nodes.iter().filter(|n| matches!(n.role, Role::Relay | Role::Sink))
+    }
+
+    /// Returns the fraction of receivers for which `f` is true, or 1 if there are none.
+    fn fraction(&self, f: impl Fn(&NodeReport) -> bool) -> f64 {
+        let (count, total) =
+            self.receivers().fold((0, 0), |(c, t), n| (c + usize::from(f(n)), t + 1));
+
+        if total == 0 { 1.0 } else { count as f64 / total as f64 }
+    }
+
+    /// Returns the fraction of receivers that decoded the data.
+    pub fn decoded_fraction(&self) -> f64 {
+        self.fraction(|n| n.decoded_at.is_some())
+    }
+
+    /// Returns the fraction of receivers that accepted a polluted packet.
+    pub fn polluted_fraction(&self) -> f64 {
+        self.fraction(|n| n.polluted_at.is_some())
+    }
+
+    /// Returns the fraction of receivers that reached full rank but decoded the wrong data.
+    pub fn failed_fraction(&self) -> f64 {
+        self.fraction(|n| n.decode_failed)
+    }
+
+    /// Returns the distribution of the time to decode in seconds, over the receivers that
+    /// decoded.
+    pub fn decode_times(&self) -> Distribution {
+        Distribution::new(
+            self.receivers().filter_map(|n| n.decoded_at).map(|t| t.as_secs_f64()).collect(),
+        )
+    }
+
+    /// Returns the distribution of the bandwidth overhead of the receivers, i.e. the bytes
+    /// received divided by the data size.
+    pub fn overhead(&self) -> Distribution {
+        Distribution::new(
+            self.receivers().map(|n| n.bytes_received as f64 / self.data_size as f64).collect(),
+        )
+    }
+
+    /// Returns the total number of bytes sent by all nodes.
+    pub fn total_bytes_sent(&self) -> usize {
+        self.nodes.iter().map(|n| n.bytes_sent).sum()
+    }
+
+    /// Returns the total number of packets rejected by verification.
+    pub fn total_rejected(&self) -> usize {
+        self.nodes.iter().map(|n| n.rejected).sum()
+    }
+
+    /// Returns the total wall-clock time spent verifying packets, and the number of packets
+    /// verified.
+    pub fn total_verify_time(&self) -> (Duration, usize) {
+        self.nodes.iter().fold((Duration::ZERO, 0), |(t, c), n| (t + n.verify_time, c + n.verified))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "decoded:        {:.1}%", self.decoded_fraction() * 100.0)?;
+        writeln!(f, "time to decode: {} s", self.decode_times())?;
+        writeln!(f, "overhead:       {}", self.overhead())?;
+        writeln!(f, "polluted:       {:.1}%", self.polluted_fraction() * 100.0)?;
+        writeln!(f, "failed:         {:.1}%", self.failed_fraction() * 100.0)?;
+
+        let (verify_time, verified) = self.total_verify_time();
+        if verified > 0 {
+            writeln!(
+                f,
+                "verified:       {verified} packets in {verify_time:?}, rejected {}",
+                self.total_rejected()
+            )?;
+        }
+
+        write!(f, "total sent:     {} bytes in {:?}", self.total_bytes_sent(), self.duration)
+    }
+}
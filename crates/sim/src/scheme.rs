@@ -0,0 +1,147 @@
+//! Coding schemes to compare RLNC against.
+use zc_rlnc::{
+    encode::Encoder,
+    primitives::{field::Field, packet::RLNCPacket},
+};
+
+use crate::simulation::SimError;
+
+/// The size in bytes of the share index that [`Scheme::Flood`] and [`Scheme::ReedSolomon`]
+/// packets carry instead of a coding vector.
+pub(crate) const SHARE_INDEX_SIZE: usize = 4;
+
+/// The maximum number of shares, such that every share index is a distinct field element.
+const MAX_SHARES: usize = 256;
+
+/// How the data is coded and propagated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scheme {
+    /// Random linear network coding: sources send random combinations of the chunks, and relays
+    /// recode.
+    #[default]
+    Rlnc,
+    /// Flood gossip of the unencoded chunks. Sources send every chunk to each neighbor, and
+    /// relays forward every chunk they see for the first time to all other neighbors.
+    Flood,
+    /// Flood gossip of the shares of a fixed-rate, systematic Reed–Solomon erasure code with
+    /// `parity` shares beyond the chunks. Any `chunk_count` distinct shares decode the data.
+    ReedSolomon {
+        /// The number of parity shares.
+        parity: usize,
+    },
+}
+
+impl Scheme {
+    /// Encodes the shares of [`Scheme::Flood`] and [`Scheme::ReedSolomon`], or returns nothing
+    /// for [`Scheme::Rlnc`].
+    pub(crate) fn shares<F: Field>(
+        self,
+        encoder: &Encoder<F>,
+    ) -> Result<Vec<RLNCPacket<F>>, SimError> {
+        let chunk_count = encoder.layout().chunk_count;
+        let count = match self {
+            Self::Rlnc => return Ok(Vec::new()),
+            Self::Flood => chunk_count,
+            Self::ReedSolomon { parity } => chunk_count + parity,
+        };
+
+        if count > MAX_SHARES {
+            return Err(SimError::TooManyShares(count));
+        }
+
+        (0..count)
+            .map(|index| Ok(encoder.encode_with_vector(&coding_vector(chunk_count, index))?))
+            .collect()
+    }
+}
+
+/// Returns the coding vector of a share: the unit vector for the first `chunk_count` shares, and
+/// the Cauchy row `1 / (xᵢ - yⱼ)` with `xᵢ = index` and `yⱼ = j` for the parity shares. Every
+/// square submatrix of a Cauchy matrix is invertible, so any `chunk_count` shares decode.
+pub(crate) fn coding_vector<F: Field>(chunk_count: usize, index: usize) -> Vec<F> {
+    let point = |i: usize| F::from_bytes(&[i as u8]);
+
+    (0..chunk_count)
+        .map(|j| {
+            if index >= chunk_count {
+                (point(index) - point(j)).invert().unwrap()
+            } else if index == j {
+                F::ONE
+            } else {
+                F::ZERO
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::{SeedableRng, rngs::StdRng, seq::index};
+    use zc_rlnc::{decode::Decoder, primitives::field::Gf256};
+
+    use super::*;
+    use crate::{LinkConfig, SimConfig, Simulation, Topology};
+
+    #[test]
+    fn test_reed_solomon_any_shares_decode() {
+        let data = (0..4096).map(|i| i as u8).collect::<Vec<_>>();
+        let encoder = Encoder::<Gf256>::new(&data, 12).unwrap();
+        let shares = Scheme::ReedSolomon { parity: 8 }.shares(&encoder).unwrap();
+        assert_eq!(shares.len(), 20);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let mut decoder = Decoder::<Gf256>::from_layout(encoder.layout()).unwrap();
+            for i in index::sample(&mut rng, shares.len(), 12) {
+                decoder.push(shares[i].clone()).unwrap();
+            }
+
+            assert!(decoder.can_decode());
+            let mut decoded = vec![0u8; decoder.decoded_len().unwrap()];
+            decoder.decode_into(&mut decoded).unwrap();
+            assert_eq!(decoded, data);
+        }
+
+        assert!(matches!(
+            Scheme::ReedSolomon { parity: 250 }.shares(&encoder),
+            Err(SimError::TooManyShares(262))
+        ));
+    }
+
+    #[test]
+    fn test_compare_schemes() {
+        let topology = Topology::random_regular(50, 6, StdRng::seed_from_u64(1)).unwrap();
+        let link = LinkConfig::new(Duration::from_millis(20), 1_250_000, 0.05);
+
+        let schemes = [Scheme::Rlnc, Scheme::Flood, Scheme::ReedSolomon { parity: 4 }];
+        let reports = schemes.map(|scheme| {
+            let config = SimConfig { scheme, link, ..Default::default() };
+            let report = Simulation::<Gf256>::new(topology.clone(), config).run().unwrap();
+
+            let times = report.decode_times();
+            println!(
+                "{scheme:?}: decoded {:.1}%, time to decode p50 {:.3}s p90 {:.3}s, mean overhead \
+                 {:.2}, total sent {} bytes",
+                report.decoded_fraction() * 100.0,
+                times.percentile(0.5).unwrap_or_default(),
+                times.percentile(0.9).unwrap_or_default(),
+                report.overhead().mean().unwrap(),
+                report.total_bytes_sent(),
+            );
+
+            report
+        });
+
+        for report in &reports {
+            assert_eq!(report.decoded_fraction(), 1.0);
+        }
+
+        // Flooding sends every chunk once over every directed edge, except back to the node it
+        // came from.
+        let edges = topology.edges().count();
+        let flood_packets = reports[1].nodes.iter().map(|n| n.packets_sent).sum::<usize>();
+        assert_eq!(flood_packets, 2 * edges * 16 - 49 * 16);
+    }
+}
@@ -0,0 +1,539 @@
+//! The discrete-event simulation of an RLNC broadcast.
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use zc_gossip::GossipError;
+use zc_rlnc::{
+    RLNCError,
+    decode::Decoder,
+    encode::Encoder,
+    primitives::{ChunkLayout, field::Field, packet::RLNCPacket},
+};
+
+use crate::{
+    adversary::{Attack, PacketVerifier},
+    link::LinkConfig,
+    queue::EventQueue,
+    report::{NodeReport, Report},
+    scheme::{self, SHARE_INDEX_SIZE, Scheme},
+    topology::Topology,
+};
+
+/// The role of a node in a broadcast.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Role {
+    /// Encodes the data with an [`Encoder`] and pushes coded packets to its neighbors.
+    Source,
+    /// Decodes the data, and forwards packets to its neighbors according to the [`Scheme`].
+    #[default]
+    Relay,
+    /// Only decodes the data.
+    Sink,
+    /// Pollutes the network: injects as many bad packets to each neighbor as a source, and
+    /// answers every received packet with a bad packet to all other neighbors.
+    Attacker(Attack),
+}
+
+/// The parameters of a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimConfig {
+    /// The size of the broadcast data in bytes.
+    pub data_size: usize,
+    /// The number of chunks (generation size).
+    pub chunk_count: usize,
+    /// How the data is coded and propagated.
+    pub scheme: Scheme,
+    /// The properties of every link, unless overridden with [`Simulation::set_link`].
+    pub link: LinkConfig,
+    /// The number of packets beyond the chunk count that sources send to every neighbor, and
+    /// that relays send to every neighbor once they decoded, to make up for losses. Only used by
+    /// [`Scheme::Rlnc`].
+    pub redundancy: usize,
+    /// The simulated time after which packets are dropped.
+    pub max_time: Duration,
+    /// The seed of the random number generator, which makes simulations reproducible.
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            data_size: 64 * 1024,
+            chunk_count: 16,
+            scheme: Scheme::Rlnc,
+            link: LinkConfig::default(),
+            redundancy: 2,
+            max_time: Duration::from_secs(60),
+            seed: 0,
+        }
+    }
+}
+
+/// Errors that can occur when running a simulation.
+#[derive(Debug, thiserror::Error)]
+pub enum SimError {
+    /// Encoding or decoding failed.
+    #[error(transparent)]
+    Coding(#[from] RLNCError),
+    /// The gossip protocol failed.
+    #[error(transparent)]
+    Gossip(#[from] GossipError),
+    /// The simulation has no source.
+    #[error("simulation has no source")]
+    NoSource,
+    /// The erasure code has more shares than there are distinct share indices.
+    #[error("too many shares: got {0}, at most 256 are supported")]
+    TooManyShares(usize),
+    /// A link has no bandwidth, or a loss that isn't a probability.
+    #[error("invalid link: {0:?}")]
+    InvalidLink(LinkConfig),
+}
+
+/// A discrete-event simulation of broadcasting random data over a [`Topology`].
+///
+/// With [`Scheme::Rlnc`], sources push `chunk_count + redundancy` coded packets to each of their
+/// neighbors. Relays forward a recoded packet to all other neighbors for every innovative packet
+/// they receive, and `redundancy` more once they decoded. Packets are recoded when they're queued
+/// on a link, not when they're sent. The other schemes are baselines that flood fixed shares, see
+/// [`Scheme`].
+///
+/// Packets sent by attackers, and packets recoded by nodes that accepted a polluted packet, are
+/// polluted. Nodes can't tell them apart from genuine packets unless they have a verifier.
+#[derive(Debug)]
+pub struct Simulation<F: Field> {
+    topology: Topology,
+    config: SimConfig,
+    roles: Vec<Role>,
+    links: HashMap<(usize, usize), LinkConfig>,
+    verifier: Option<Box<dyn PacketVerifier<F>>>,
+    _field: PhantomData<F>,
+}
+
+impl<F: Field> Simulation<F> {
+    /// Creates a new simulation, where node 0 is the source and all other nodes are relays.
+    pub fn new(topology: Topology, config: SimConfig) -> Self {
+        let mut roles = vec![Role::Relay; topology.len()];
+        if let Some(source) = roles.first_mut() {
+            *source = Role::Source;
+        }
+
+        Self { topology, config, roles, links: HashMap::new(), verifier: None, _field: PhantomData }
+    }
+
+    /// Returns the topology.
+    pub const fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    /// Sets the role of a node.
+    pub fn set_role(&mut self, node: usize, role: Role) {
+        self.roles[node] = role;
+    }
+
+    /// Overrides the properties of the directed link from `from` to `to`.
+    pub fn set_link(&mut self, from: usize, to: usize, link: LinkConfig) {
+        self.links.insert((from, to), link);
+    }
+
+    /// Makes honest nodes verify every packet before accepting it. The wall-clock time spent
+    /// verifying is reported, but doesn't advance the simulated time.
+    pub fn set_verifier(&mut self, verifier: impl PacketVerifier<F> + 'static) {
+        self.verifier = Some(Box::new(verifier));
+    }
+
+    /// Returns the random data that is broadcast, which is derived from the seed. Use it to set
+    /// up a verifier.
+    pub fn data(&self) -> Vec<u8> {
+        StdRng::seed_from_u64(self.config.seed).random_iter().take(self.config.data_size).collect()
+    }
+
+    /// Runs the simulation until no packets are in flight, or the maximum time is reached.
+    pub fn run(&self) -> Result<Report, SimError> {
+        // The data is drawn first, see `Simulation::data`.
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+        let data = (&mut rng).random_iter().take(self.config.data_size).collect::<Vec<u8>>();
+
+        let encoder = Encoder::<F>::new(&data, self.config.chunk_count)?;
+        let layout = encoder.layout();
+        let shares = self.config.scheme.shares(&encoder)?;
+
+        // Shares carry their index instead of a coding vector.
+        let packet_size = match self.config.scheme {
+            Scheme::Rlnc => layout.packet_size,
+            Scheme::Flood | Scheme::ReedSolomon { .. } => {
+                layout.packet_size - layout.chunk_count * F::ENCODED_SIZE + SHARE_INDEX_SIZE
+            }
+        };
+
+        let nodes = self
+            .roles
+            .iter()
+            .map(|&role| {
+                // Attackers keep the genuine packets they receive to craft bad ones.
+                let decoder = match role {
+                    Role::Source => None,
+                    Role::Relay | Role::Sink | Role::Attacker(_) => {
+                        Some(Decoder::from_layout(layout)?)
+                    }
+                };
+
+                Ok(Node {
+                    decoder,
+                    seen: vec![false; shares.len()],
+                    report: NodeReport { role, ..Default::default() },
+                })
+            })
+            .collect::<Result<Vec<_>, RLNCError>>()?;
+
+        let mut run = Run {
+            simulation: self,
+            encoder,
+            data,
+            layout,
+            packet_size,
+            shares,
+            nodes,
+            queue: EventQueue::new(&self.links, self.config.link)?,
+            now: Duration::ZERO,
+            rng,
+        };
+
+        if !self.roles.contains(&Role::Source) {
+            return Err(SimError::NoSource);
+        }
+
+        for (node, &role) in self.roles.iter().enumerate() {
+            match role {
+                Role::Source | Role::Attacker(_) if self.config.scheme == Scheme::Rlnc => {
+                    run.forward(node, None, self.config.chunk_count + self.config.redundancy)?;
+                }
+                Role::Source => {
+                    for share in 0..run.shares.len() {
+                        run.spread(node, None, share, run.shares[share].clone(), false);
+                    }
+                }
+                Role::Attacker(attack) => {
+                    for share in 0..run.shares.len() {
+                        run.inject(node, None, attack, share);
+                    }
+                }
+                Role::Relay | Role::Sink => {}
+            }
+        }
+
+        while let Some((time, delivery)) = run.queue.pop() {
+            if time > self.config.max_time {
+                break;
+            }
+
+            run.now = time;
+            run.deliver(delivery)?;
+        }
+
+        Ok(Report {
+            nodes: run.nodes.into_iter().map(|n| n.report).collect(),
+            data_size: self.config.data_size,
+            duration: run.now,
+        })
+    }
+}
+
+/// The state of a node during a simulation.
+#[derive(Debug)]
+struct Node<F: Field> {
+    /// The decoder of receivers, or `None` for sources.
+    decoder: Option<Decoder<F>>,
+    /// The shares the node accepted, with share-based schemes.
+    seen: Vec<bool>,
+    report: NodeReport,
+}
+
+/// A packet arriving at a node.
+#[derive(Debug)]
+struct Delivery<F: Field> {
+    from: usize,
+    to: usize,
+    packet: RLNCPacket<F>,
+    /// The share index, with share-based schemes.
+    share: Option<usize>,
+    /// Whether the packet is polluted, which nodes can't see.
+    polluted: bool,
+}
+
+/// The state of a running simulation.
+struct Run<'a, F: Field> {
+    simulation: &'a Simulation<F>,
+    encoder: Encoder<F>,
+    data: Vec<u8>,
+    layout: ChunkLayout,
+    /// The size of a packet on the wire.
+    packet_size: usize,
+    /// The shares of share-based schemes.
+    shares: Vec<RLNCPacket<F>>,
+    nodes: Vec<Node<F>>,
+    queue: EventQueue<'a, Delivery<F>>,
+    now: Duration,
+    rng: StdRng,
+}
+
+impl<F: Field> Run<'_, F> {
+    /// Handles a packet arriving at a node.
+    fn deliver(&mut self, delivery: Delivery<F>) -> Result<(), SimError> {
+        let Delivery { from, to, packet, share, polluted } = delivery;
+
+        let node = &mut self.nodes[to];
+        node.report.packets_received += 1;
+        node.report.bytes_received += self.packet_size;
+
+        let role = node.report.role;
+        match (role, &mut node.decoder) {
+            (Role::Source, _) | (_, None) => return Ok(()),
+            (Role::Attacker(attack), Some(decoder)) => {
+                if !polluted {
+                    decoder.push(packet)?;
+                }
+
+                return match share {
+                    Some(_) => {
+                        let share = self.rng.random_range(0..self.shares.len());
+                        self.inject(to, Some(from), attack, share);
+                        Ok(())
+                    }
+                    None => self.forward(to, Some(from), 1),
+                };
+            }
+            (Role::Relay | Role::Sink, Some(_)) => {}
+        }
+
+        if share.is_some_and(|share| node.seen[share]) || !self.verify(to, &packet) {
+            return Ok(());
+        }
+
+        if let Some(share) = share {
+            self.nodes[to].seen[share] = true;
+            if role == Role::Relay {
+                self.spread(to, Some(from), share, packet.clone(), polluted);
+            }
+        }
+
+        let Some(decoded) = self.absorb(to, packet, polluted)? else {
+            return Ok(());
+        };
+
+        if role == Role::Relay && share.is_none() {
+            self.forward(to, Some(from), 1)?;
+
+            // A relay can't tell whether it decoded the right data.
+            if decoded {
+                self.forward(to, None, self.simulation.config.redundancy)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a packet if the simulation has a verifier, and returns true if it's accepted.
+    fn verify(&mut self, node: usize, packet: &RLNCPacket<F>) -> bool {
+        let Some(verifier) = &self.simulation.verifier else {
+            return true;
+        };
+
+        let report = &mut self.nodes[node].report;
+        let start = Instant::now();
+        let valid = verifier.verify(packet);
+        report.verify_time += start.elapsed();
+        report.verified += 1;
+
+        if !valid {
+            report.rejected += 1;
+        }
+
+        valid
+    }
+
+    /// Pushes an accepted packet into the decoder of a node. Returns `None` if the packet isn't
+    /// innovative, or whether the node reached full rank.
+    fn absorb(
+        &mut self,
+        node: usize,
+        packet: RLNCPacket<F>,
+        polluted: bool,
+    ) -> Result<Option<bool>, SimError> {
+        let Node { decoder: Some(decoder), report, .. } = &mut self.nodes[node] else {
+            return Ok(None);
+        };
+
+        let rank = decoder.rank();
+        decoder.push(packet)?;
+        if decoder.rank() == rank {
+            return Ok(None);
+        }
+
+        report.innovative += 1;
+        if polluted {
+            report.polluted_at.get_or_insert(self.now);
+        }
+
+        let decoded = decoder.can_decode();
+        if decoded {
+            // Polluted data may not even have valid padding.
+            let correct = decoder
+                .decoded_len()
+                .and_then(|len| {
+                    let mut buf = vec![0u8; len];
+                    decoder.decode_into(&mut buf).map(|_| buf)
+                })
+                .is_ok_and(|buf| buf == self.data);
+
+            if correct {
+                report.decoded_at = Some(self.now);
+            } else {
+                report.decode_failed = true;
+            }
+        }
+
+        Ok(Some(decoded))
+    }
+
+    /// Sends a share from a node to each of its neighbors, except `exclude`.
+    fn spread(
+        &mut self,
+        node: usize,
+        exclude: Option<usize>,
+        share: usize,
+        packet: RLNCPacket<F>,
+        polluted: bool,
+    ) {
+        let neighbors = self.simulation.topology.neighbors(node);
+
+        for &neighbor in neighbors.iter().filter(|&&n| Some(n) != exclude) {
+            self.send(node, neighbor, packet.clone(), Some(share), polluted);
+        }
+    }
+
+    /// Sends a bad share from an attacker to each of its neighbors, except `exclude`.
+    fn inject(&mut self, node: usize, exclude: Option<usize>, attack: Attack, share: usize) {
+        let mut packet =
+            attack.packet(self.layout, self.nodes[node].decoder.as_ref(), &mut self.rng);
+        packet.coding_vector = scheme::coding_vector(self.layout.chunk_count, share);
+
+        self.spread(node, exclude, share, packet, true);
+    }
+
+    /// Sends `count` coded packets from a node to each of its neighbors, except `exclude`.
+    fn forward(
+        &mut self,
+        node: usize,
+        exclude: Option<usize>,
+        count: usize,
+    ) -> Result<(), SimError> {
+        let neighbors = self.simulation.topology.neighbors(node);
+
+        for &neighbor in neighbors.iter().filter(|&&n| Some(n) != exclude) {
+            for _ in 0..count {
+                let state = &self.nodes[node];
+                let (packet, polluted) = match (state.report.role, &state.decoder) {
+                    (Role::Attacker(attack), decoder) => {
+                        (attack.packet(self.layout, decoder.as_ref(), &mut self.rng), true)
+                    }
+                    (_, Some(decoder)) => {
+                        (decoder.recode(&mut self.rng)?, state.report.polluted_at.is_some())
+                    }
+                    (_, None) => (self.encoder.encode(&mut self.rng)?, false),
+                };
+
+                self.send(node, neighbor, packet, None, polluted);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queues a packet on the link from `from` to `to`.
+    fn send(
+        &mut self,
+        from: usize,
+        to: usize,
+        packet: RLNCPacket<F>,
+        share: Option<usize>,
+        polluted: bool,
+    ) {
+        let size = self.packet_size;
+        let report = &mut self.nodes[from].report;
+        report.packets_sent += 1;
+        report.bytes_sent += size;
+
+        let delivery = Delivery { from, to, packet, share, polluted };
+        self.queue.send(from, to, self.now, size, &mut self.rng, delivery);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zc_rlnc::primitives::field::Gf256;
+
+    use super::*;
+
+    #[test]
+    fn test_simulate_random_regular() {
+        let topology = Topology::random_regular(50, 6, StdRng::seed_from_u64(1)).unwrap();
+        let config = SimConfig {
+            link: LinkConfig::new(Duration::from_millis(20), 1_250_000, 0.01)
+                .with_jitter(Duration::from_millis(5)),
+            ..Default::default()
+        };
+
+        let report = Simulation::<Gf256>::new(topology, config).run().unwrap();
+        println!("{report}");
+
+        assert_eq!(report.decoded_fraction(), 1.0);
+        assert!(report.overhead().min().unwrap() >= 1.0);
+        assert_eq!(report.nodes[0].innovative, 0);
+    }
+
+    #[test]
+    fn test_simulate_clustered() {
+        let topology = Topology::clustered(4, 10, 0.5, 0.02, StdRng::seed_from_u64(2));
+        assert!(topology.is_connected());
+
+        let mut simulation = Simulation::<Gf256>::new(topology.clone(), SimConfig::default());
+
+        // Links between clusters are slower, and some nodes only listen.
+        let slow = LinkConfig::new(Duration::from_millis(150), 1_250_000, 0.05);
+        for (a, b) in topology.edges().filter(|&(a, b)| topology.cluster(a) != topology.cluster(b))
+        {
+            simulation.set_link(a, b, slow);
+            simulation.set_link(b, a, slow);
+        }
+
+        for sink in (5..40).step_by(10) {
+            simulation.set_role(sink, Role::Sink);
+        }
+
+        let report = simulation.run().unwrap();
+        println!("{report}");
+
+        assert_eq!(report.decoded_fraction(), 1.0);
+        assert_eq!(report.nodes[5].packets_sent, 0);
+
+        // The same seed gives the same results.
+        assert_eq!(simulation.run().unwrap(), report);
+    }
+
+    #[test]
+    fn test_invalid_link() {
+        let topology = Topology::from_edges(2, [(0, 1)]);
+        let link = LinkConfig::new(Duration::from_millis(20), 0, 0.0);
+        let config = SimConfig { link, ..Default::default() };
+        let result = Simulation::<Gf256>::new(topology.clone(), config).run();
+        assert!(matches!(result, Err(SimError::InvalidLink(l)) if l == link));
+
+        let mut simulation = Simulation::<Gf256>::new(topology, SimConfig::default());
+        simulation.set_link(1, 0, LinkConfig::new(Duration::from_millis(20), 1_250_000, 1.5));
+        assert!(matches!(simulation.run(), Err(SimError::InvalidLink(_))));
+    }
+}
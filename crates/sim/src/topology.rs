@@ -0,0 +1,189 @@
+//! Network topologies.
+use std::collections::{BTreeSet, VecDeque};
+
+use rand::Rng;
+
+/// The maximum number of attempts to generate a random regular graph before giving up.
+const MAX_REGULAR_ATTEMPTS: usize = 1000;
+
+/// An undirected graph of peers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topology {
+    /// The neighbors of every node, sorted.
+    neighbors: Vec<Vec<usize>>,
+    /// The cluster of every node.
+    clusters: Vec<usize>,
+}
+
+impl Topology {
+    /// Creates a topology from a list of undirected edges between `n` nodes. Self-loops and
+    /// duplicate edges are ignored.
+    ///
+    /// # Panics
+    /// Panics if an edge refers to a node that doesn't exist.
+    pub fn from_edges(n: usize, edges: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut neighbors = vec![BTreeSet::new(); n];
+        for (a, b) in edges {
+            assert!(a < n && b < n, "edge ({a}, {b}) out of bounds");
+            if a != b {
+                neighbors[a].insert(b);
+                neighbors[b].insert(a);
+            }
+        }
+
+        Self {
+            neighbors: neighbors.into_iter().map(|n| n.into_iter().collect()).collect(),
+            clusters: vec![0; n],
+        }
+    }
+
+    /// Generates a random `degree`-regular graph with `n` nodes, by repeatedly pairing two random
+    /// free stubs that don't form a self-loop or duplicate edge (Steger–Wormald). Returns `None`
+    /// if `n * degree` is odd, `degree >= n`, or no simple graph was found.
+    pub fn random_regular<R: Rng>(n: usize, degree: usize, mut rng: R) -> Option<Self> {
+        if !(n * degree).is_multiple_of(2) || degree >= n {
+            return None;
+        }
+
+        'attempt: for _ in 0..MAX_REGULAR_ATTEMPTS {
+            let mut stubs = (0..n).flat_map(|i| std::iter::repeat_n(i, degree)).collect::<Vec<_>>();
+            let mut edges = BTreeSet::new();
+
+            while !stubs.is_empty() {
+                // Near the end, the remaining stubs may not allow any valid pair, so give up on
+                // the attempt after enough misses.
+                let paired = (0..stubs.len() * stubs.len()).find_map(|_| {
+                    let (i, j) =
+                        (rng.random_range(0..stubs.len()), rng.random_range(0..stubs.len()));
+                    let (a, b) = (stubs[i].min(stubs[j]), stubs[i].max(stubs[j]));
+                    (a != b && !edges.contains(&(a, b))).then_some((i.max(j), i.min(j), (a, b)))
+                });
+
+                let Some((i, j, edge)) = paired else {
+                    continue 'attempt;
+                };
+
+                edges.insert(edge);
+                stubs.swap_remove(i);
+                stubs.swap_remove(j);
+            }
+
+            return Some(Self::from_edges(n, edges));
+        }
+
+        None
+    }
+
+    /// Generates an Erdős–Rényi graph with `n` nodes, where every edge exists independently with
+    /// probability `p`.
+    pub fn erdos_renyi<R: Rng>(n: usize, p: f64, mut rng: R) -> Self {
+        let edges = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .filter(|_| rng.random_bool(p))
+            .collect::<Vec<_>>();
+
+        Self::from_edges(n, edges)
+    }
+
+    /// Generates a clustered graph with `clusters` clusters of `size` nodes each. Nodes in the
+    /// same cluster are connected with probability `p_intra`, and nodes in different clusters with
+    /// probability `p_inter`, which is usually much smaller. Node `i` is in cluster `i / size`.
+    pub fn clustered<R: Rng>(
+        clusters: usize,
+        size: usize,
+        p_intra: f64,
+        p_inter: f64,
+        mut rng: R,
+    ) -> Self {
+        let n = clusters * size;
+        let edges = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .filter(|(a, b)| rng.random_bool(if a / size == b / size { p_intra } else { p_inter }))
+            .collect::<Vec<_>>();
+
+        let mut topology = Self::from_edges(n, edges);
+        topology.clusters = (0..n).map(|i| i / size).collect();
+        topology
+    }
+
+    /// Returns the number of nodes.
+    pub const fn len(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    /// Returns true if there are no nodes.
+    pub const fn is_empty(&self) -> bool {
+        self.neighbors.is_empty()
+    }
+
+    /// Returns the neighbors of a node.
+    pub fn neighbors(&self, node: usize) -> &[usize] {
+        &self.neighbors[node]
+    }
+
+    /// Returns the cluster of a node. Topologies without clusters have a single cluster 0.
+    pub fn cluster(&self, node: usize) -> usize {
+        self.clusters[node]
+    }
+
+    /// Returns an iterator over all undirected edges `(a, b)` with `a < b`.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors
+            .iter()
+            .enumerate()
+            .flat_map(|(a, n)| n.iter().filter(move |&&b| a < b).map(move |&b| (a, b)))
+    }
+
+    /// Returns true if every node is reachable from every other node.
+    pub fn is_connected(&self) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        let mut visited = vec![false; self.len()];
+        let mut queue = VecDeque::from([0]);
+        visited[0] = true;
+
+        while let Some(node) = queue.pop_front() {
+            for &neighbor in &self.neighbors[node] {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        visited.into_iter().all(|v| v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn test_topologies() {
+        let regular = Topology::random_regular(100, 8, StdRng::seed_from_u64(0)).unwrap();
+        assert!((0..100).all(|i| regular.neighbors(i).len() == 8));
+        assert_eq!(regular.edges().count(), 400);
+        assert!(regular.is_connected());
+        assert!(Topology::random_regular(5, 3, StdRng::seed_from_u64(0)).is_none());
+
+        let random = Topology::erdos_renyi(100, 0.1, StdRng::seed_from_u64(0));
+        let edges = random.edges().count();
+        assert!((300..700).contains(&edges), "{edges} edges");
+
+        let clustered = Topology::clustered(4, 25, 0.3, 0.01, StdRng::seed_from_u64(0));
+        let inter = clustered
+            .edges()
+            .filter(|&(a, b)| clustered.cluster(a) != clustered.cluster(b))
+            .count();
+        assert!(inter < clustered.edges().count() / 4);
+
+        let line = Topology::from_edges(3, [(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(line.edges().collect::<Vec<_>>(), [(0, 1)]);
+        assert!(!line.is_connected());
+    }
+}